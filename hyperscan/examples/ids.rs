@@ -0,0 +1,525 @@
+// Hyperscan example program 4: ids
+//
+// This example is a minimal intrusion detection pipeline, wiring the pieces
+// of the crate together as a reference architecture:
+//
+//  - the ruleset is loaded from an `id:/expr/flags` pattern file, where a
+//    `# msg: ...` comment attaches a message to the following rule, and the
+//    rules with the `L` flag report their start of match offset;
+//  - the ruleset is reloaded when the pattern file changes, the streams of the
+//    old ruleset are shut down and their end of data matches are reported;
+//  - the packets are read from a PCAP file or received from a UDP socket;
+//  - the payloads go through a transform pipeline (e.g. lowercase, strip NULs)
+//    before being scanned in the `StreamMap` stream of their session;
+//  - the matches are written to a sink as JSON lines, which also keeps some
+//    metrics reported on exit.
+//
+// Build instructions:
+//
+//     cargo run --example ids -- <pattern file> --pcap <pcap file>
+//
+// The pipeline is tested against the bundled `ids.patterns` and `ids.pcap`:
+//
+//     cargo test --example ids
+//
+// Usage:
+//
+//     ./ids [--lowercase] [--strip-nul] [--reload <secs>] [--grace <ms>] <pattern file> (--pcap <file> | --udp <addr>)
+//
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::net::{SocketAddr, SocketAddrV4, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use byteorder::{BigEndian, ReadBytesExt};
+use pnet::packet::{
+    ethernet::{EtherTypes, EthernetPacket},
+    ip::IpNextHeaderProtocols,
+    ipv4::Ipv4Packet,
+    udp::UdpPacket,
+    Packet, PrimitiveValues,
+};
+use structopt::StructOpt;
+
+use hyperscan::prelude::*;
+use hyperscan::{ShutdownReport, StreamMap};
+
+// Key for identifying a stream, using data from its IP headers.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct Session {
+    proto: u8,
+    src: SocketAddrV4,
+    dst: SocketAddrV4,
+}
+
+const IP_FLAG_MF: u8 = 1;
+
+// Decode a TCP or UDP payload from an ethernet frame, ignoring the IP fragments.
+fn decode_packet(data: &[u8]) -> Option<(Session, Vec<u8>)> {
+    let ether = EthernetPacket::new(data)?;
+
+    if ether.get_ethertype() != EtherTypes::Ipv4 {
+        return None;
+    }
+
+    let ipv4 = Ipv4Packet::new(ether.payload())?;
+
+    if ipv4.get_version() != 4 || (ipv4.get_flags() & IP_FLAG_MF) == IP_FLAG_MF || ipv4.get_fragment_offset() != 0 {
+        return None;
+    }
+
+    let mut c = io::Cursor::new(ipv4.payload());
+    let session = Session {
+        proto: ipv4.get_next_level_protocol().to_primitive_values().0,
+        src: SocketAddrV4::new(ipv4.get_source(), c.read_u16::<BigEndian>().ok()?),
+        dst: SocketAddrV4::new(ipv4.get_destination(), c.read_u16::<BigEndian>().ok()?),
+    };
+
+    match ipv4.get_next_level_protocol() {
+        IpNextHeaderProtocols::Tcp => {
+            let payload = ipv4.payload();
+            let data_off = ((payload.get(12)? >> 4) * 4) as usize;
+
+            Some((session, Vec::from(payload.get(data_off..)?)))
+        }
+        IpNextHeaderProtocols::Udp => {
+            let udp = UdpPacket::new(ipv4.payload())?;
+
+            Some((session, Vec::from(udp.payload())))
+        }
+        _ => None,
+    }
+}
+
+/// The next packet of a source.
+enum Next {
+    /// The payload of a TCP or UDP packet, and its session.
+    Payload(Session, Vec<u8>),
+    /// A packet without payload, or a receive timeout.
+    Skip,
+    /// The source is exhausted.
+    End,
+}
+
+/// The packet sources of the pipeline.
+enum Source {
+    Pcap(pcap::Capture<pcap::Offline>),
+    Udp(UdpSocket, SocketAddrV4),
+}
+
+impl Source {
+    fn open(opt: &Opt) -> Result<Source> {
+        match (&opt.pcap, &opt.udp) {
+            (Some(path), None) => Ok(Source::Pcap(
+                pcap::Capture::from_file(path).with_context(|| format!("open {:?}", path))?,
+            )),
+            (None, Some(addr)) => {
+                let socket = UdpSocket::bind(addr).with_context(|| format!("bind {}", addr))?;
+                let local = match socket.local_addr()? {
+                    SocketAddr::V4(addr) => addr,
+                    SocketAddr::V6(_) => bail!("only IPv4 is supported"),
+                };
+
+                // wake up periodically to check the pattern file for changes
+                socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+
+                Ok(Source::Udp(socket, local))
+            }
+            _ => bail!("either `--pcap` or `--udp` is required"),
+        }
+    }
+
+    fn next(&mut self) -> Result<Next> {
+        match self {
+            Source::Pcap(capture) => {
+                match capture.next() {
+                    Ok(packet) => Ok(decode_packet(packet.data)
+                        .map_or(Next::Skip, |(session, payload)| Next::Payload(session, payload))),
+                    Err(pcap::Error::NoMorePackets) => Ok(Next::End),
+                    Err(err) => Err(err.into()),
+                }
+            }
+            Source::Udp(socket, local) => {
+                let mut buf = vec![0; u16::max_value() as usize];
+
+                match socket.recv_from(&mut buf) {
+                    Ok((len, SocketAddr::V4(src))) => {
+                        buf.truncate(len);
+
+                        Ok(Next::Payload(
+                            Session {
+                                proto: IpNextHeaderProtocols::Udp.to_primitive_values().0,
+                                src,
+                                dst: *local,
+                            },
+                            buf,
+                        ))
+                    }
+                    Ok(_) => Ok(Next::Skip),
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock || err.kind() == io::ErrorKind::TimedOut => {
+                        Ok(Next::Skip)
+                    }
+                    Err(err) => Err(err.into()),
+                }
+            }
+        }
+    }
+}
+
+/// The compiled ruleset with the messages of the rules.
+struct Ruleset {
+    streams: StreamMap<Session>,
+    messages: HashMap<u32, String>,
+    modified: SystemTime,
+}
+
+impl Ruleset {
+    fn load<P: AsRef<Path>>(path: P) -> Result<Ruleset> {
+        let path = path.as_ref();
+        let modified = fs::metadata(path)?.modified()?;
+        let patterns = Patterns::from_file(path)?;
+        let mut messages = HashMap::new();
+        let mut msg = None;
+
+        // The patterns are parsed by `Patterns::from_file`, only the metadata is collected here.
+        for line in fs::read_to_string(path)?.lines().map(str::trim) {
+            if let Some(s) = line.strip_prefix("# msg:") {
+                msg = Some(s.trim().to_owned());
+            } else if !line.is_empty() && !line.starts_with('#') {
+                if let (Some(msg), Some(Ok(id))) = (msg.take(), line.split(":/").next().map(str::parse::<u32>)) {
+                    messages.insert(id, msg);
+                }
+            }
+        }
+
+        let db: StreamingDatabase = patterns.build()?;
+
+        Ok(Ruleset {
+            streams: StreamMap::new(db)?,
+            messages,
+            modified,
+        })
+    }
+
+    fn is_modified<P: AsRef<Path>>(&self, path: P) -> bool {
+        fs::metadata(path)
+            .and_then(|md| md.modified())
+            .map_or(false, |modified| modified != self.modified)
+    }
+}
+
+/// A transform of the payloads before scanning.
+type Transform = for<'a> fn(&'a [u8]) -> Cow<'a, [u8]>;
+
+fn lowercase(data: &[u8]) -> Cow<'_, [u8]> {
+    if data.iter().any(u8::is_ascii_uppercase) {
+        Cow::Owned(data.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(data)
+    }
+}
+
+fn strip_nul(data: &[u8]) -> Cow<'_, [u8]> {
+    if data.contains(&0) {
+        Cow::Owned(data.iter().copied().filter(|&b| b != 0).collect())
+    } else {
+        Cow::Borrowed(data)
+    }
+}
+
+/// The match sink writing the matches as JSON lines, and keeping the metrics.
+struct Sink<W> {
+    out: W,
+    packets: u64,
+    bytes: u64,
+    scan_time: Duration,
+    reloads: u64,
+    dropped: usize,
+    rules: HashMap<u32, u64>,
+}
+
+impl<W: Write> Sink<W> {
+    fn new(out: W) -> Self {
+        Sink {
+            out,
+            packets: 0,
+            bytes: 0,
+            scan_time: Duration::default(),
+            reloads: 0,
+            dropped: 0,
+            rules: HashMap::new(),
+        }
+    }
+
+    fn on_match(&mut self, ruleset: &Ruleset, session: &Session, id: u32, from: u64, to: u64) -> Matching {
+        *self.rules.entry(id).or_default() += 1;
+
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+        let msg = ruleset.messages.get(&id).map_or("", String::as_str);
+
+        if let Err(err) = writeln!(
+            self.out,
+            r#"{{"ts":{:.6},"rule":{},"msg":"{}","proto":{},"src":"{}","dst":"{}","from":{},"to":{}}}"#,
+            ts,
+            id,
+            escape(msg),
+            session.proto,
+            session.src,
+            session.dst,
+            from,
+            to
+        ) {
+            eprintln!("ERROR: Unable to write match: {}", err);
+
+            return Matching::Terminate;
+        }
+
+        Matching::Continue
+    }
+
+    fn report<E: Write>(&self, mut out: E) -> io::Result<()> {
+        let mut rules = self.rules.iter().collect::<Vec<_>>();
+
+        rules.sort();
+
+        writeln!(out, "packets: {}, bytes: {}", self.packets, self.bytes)?;
+        writeln!(
+            out,
+            "scan time: {:?}, throughput: {:.2} Mbit/s",
+            self.scan_time,
+            self.bytes as f64 * 8.0 / 1_000_000.0 / self.scan_time.as_secs_f64().max(f64::EPSILON)
+        )?;
+        writeln!(out, "reloads: {}, dropped streams: {}", self.reloads, self.dropped)?;
+
+        for (id, count) in rules {
+            writeln!(out, "rule {}: {} matches", id, count)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn escape(s: &str) -> Cow<'_, str> {
+    if s.chars().any(|c| c == '"' || c == '\\' || c.is_control()) {
+        Cow::Owned(s.chars().fold(String::with_capacity(s.len()), |mut escaped, c| {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                c => escaped.push(c),
+            }
+            escaped
+        }))
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+// Shut down the streams of the ruleset, reporting their end of data matches.
+fn shutdown<W: Write>(ruleset: &Ruleset, sink: &mut Sink<W>, grace: Duration) -> Result<ShutdownReport> {
+    let report = ruleset
+        .streams
+        .shutdown(Instant::now() + grace, |session, id, from, to| {
+            sink.on_match(ruleset, session, id, from, to)
+        })?;
+
+    sink.dropped += report.dropped + report.in_flight;
+
+    if report.failed > 0 {
+        eprintln!("WARNING: Unable to close {} streams.", report.failed);
+    }
+
+    if report.in_flight > 0 {
+        eprintln!(
+            "WARNING: {} streams still scanning after the grace period.",
+            report.in_flight
+        );
+    }
+
+    Ok(report)
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "ids", about = "A minimal intrusion detection pipeline.")]
+struct Opt {
+    /// lowercase the payloads before scanning
+    #[structopt(long)]
+    lowercase: bool,
+
+    /// strip the NUL bytes of the payloads before scanning
+    #[structopt(long)]
+    strip_nul: bool,
+
+    /// interval in seconds to check the pattern file for changes
+    #[structopt(long, default_value = "5")]
+    reload: u64,
+
+    /// grace period in milliseconds to deliver the end of data matches on shutdown
+    #[structopt(long, default_value = "1000")]
+    grace: u64,
+
+    /// pcap file
+    #[structopt(long, parse(from_os_str))]
+    pcap: Option<PathBuf>,
+
+    /// UDP address to receive the payloads
+    #[structopt(long)]
+    udp: Option<SocketAddr>,
+
+    /// pattern file
+    #[structopt(parse(from_os_str))]
+    pattern_file: PathBuf,
+}
+
+// Run the pipeline until the source is exhausted.
+fn run<W: Write>(opt: &Opt, mut ruleset: Ruleset, sink: &mut Sink<W>) -> Result<()> {
+    let mut source = Source::open(opt)?;
+
+    let mut transforms: Vec<Transform> = vec![];
+
+    if opt.strip_nul {
+        transforms.push(strip_nul);
+    }
+    if opt.lowercase {
+        transforms.push(lowercase);
+    }
+
+    let grace = Duration::from_millis(opt.grace);
+    let reload = Duration::from_secs(opt.reload);
+    let mut checked = Instant::now();
+
+    loop {
+        let packet = source.next()?;
+
+        if checked.elapsed() >= reload {
+            checked = Instant::now();
+
+            if ruleset.is_modified(&opt.pattern_file) {
+                match Ruleset::load(&opt.pattern_file) {
+                    Ok(new) => {
+                        let old = std::mem::replace(&mut ruleset, new);
+                        let report = shutdown(&old, sink, grace)?;
+
+                        sink.reloads += 1;
+
+                        eprintln!("Ruleset reloaded, {} streams closed.", report.closed + report.dropped);
+                    }
+                    Err(err) => eprintln!("ERROR: Unable to reload ruleset, keep the current one: {:#}", err),
+                }
+            }
+        }
+
+        let (session, payload) = match packet {
+            Next::Payload(session, payload) if !payload.is_empty() => (session, payload),
+            Next::Payload(..) | Next::Skip => continue,
+            Next::End => break,
+        };
+
+        let payload = transforms.iter().fold(Cow::Borrowed(&payload[..]), |data, transform| {
+            let transformed = match transform(&data) {
+                Cow::Owned(transformed) => Some(transformed),
+                Cow::Borrowed(_) => None,
+            };
+
+            transformed.map_or(data, Cow::Owned)
+        });
+
+        sink.packets += 1;
+        sink.bytes += payload.len() as u64;
+
+        let now = Instant::now();
+
+        ruleset
+            .streams
+            .scan(session, &*payload, |id, from, to, _| {
+                sink.on_match(&ruleset, &session, id, from, to)
+            })
+            .with_context(|| format!("scan {:?}", session))?;
+
+        sink.scan_time += now.elapsed();
+    }
+
+    shutdown(&ruleset, sink, grace)?;
+
+    Ok(())
+}
+
+// Main entry point.
+fn main() -> Result<()> {
+    let opt = Opt::from_args();
+
+    let ruleset = match Ruleset::load(&opt.pattern_file) {
+        Ok(ruleset) => ruleset,
+        Err(err) => {
+            eprintln!("ERROR: Unable to load ruleset: {:#}", err);
+            exit(-1);
+        }
+    };
+
+    let stdout = io::stdout();
+    let mut sink = Sink::new(BufWriter::new(stdout.lock()));
+
+    run(&opt, ruleset, &mut sink)?;
+
+    sink.out.flush()?;
+    sink.report(io::stderr())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use super::*;
+
+    fn run_fixture(args: &[&str]) -> Sink<Vec<u8>> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+        let mut argv = vec![OsString::from("ids")];
+
+        argv.extend(args.iter().map(OsString::from));
+        argv.push("--pcap".into());
+        argv.push(dir.join("ids.pcap").into());
+        argv.push(dir.join("ids.patterns").into());
+
+        let opt = Opt::from_iter(argv);
+        let ruleset = Ruleset::load(&opt.pattern_file).unwrap();
+        let mut sink = Sink::new(vec![]);
+
+        run(&opt, ruleset, &mut sink).unwrap();
+
+        sink
+    }
+
+    #[test]
+    fn test_ids_pcap() {
+        let sink = run_fixture(&[]);
+        let out = String::from_utf8(sink.out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+
+        assert_eq!(sink.packets, 4);
+        assert_eq!(sink.bytes, 43);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(
+            r#""rule":1,"msg":"admin page","proto":17,"src":"10.0.0.1:1234","dst":"10.0.0.2:53","from":0,"to":10"#
+        ));
+        assert!(lines[1].contains(
+            r#""rule":2,"msg":"shell command","proto":6,"src":"10.0.0.3:4444","dst":"10.0.0.4:80","from":6,"to":13"#
+        ));
+    }
+
+    #[test]
+    fn test_ids_transforms() {
+        let sink = run_fixture(&["--lowercase"]);
+
+        assert_eq!(sink.rules[&1], 2);
+        assert_eq!(sink.rules[&2], 1);
+    }
+}
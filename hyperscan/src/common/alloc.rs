@@ -0,0 +1,170 @@
+use std::panic;
+use std::ptr;
+use std::sync::RwLock;
+
+use anyhow::Result;
+use libc::c_void;
+
+use crate::errors::AsResult;
+use crate::ffi;
+
+/// A memory allocator which can be used by Hyperscan instead of the system `malloc()` and `free()`.
+///
+/// The memory returned by `alloc` must be at least 8-byte aligned,
+/// and a null pointer should be returned if the allocation fails.
+/// A panic in `alloc` is reported to Hyperscan as a failed allocation, and a panic in `free` leaks the region,
+/// since a panic must not unwind into Hyperscan.
+///
+/// The allocators are global to the process, and should be set before any database,
+/// scratch space or stream is allocated, since a region must be freed by the allocator which allocated it.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::Allocator;
+/// static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+///
+/// struct Counting;
+///
+/// impl Allocator for Counting {
+///     fn alloc(size: usize) -> *mut u8 {
+///         ALLOCATED.fetch_add(1, Ordering::SeqCst);
+///
+///         unsafe { libc::malloc(size) as *mut _ }
+///     }
+///
+///     unsafe fn free(ptr: *mut u8) {
+///         libc::free(ptr as *mut _)
+///     }
+/// }
+///
+/// unsafe { hyperscan::set_database_allocator::<Counting>() }.unwrap();
+///
+/// let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+///
+/// assert!(ALLOCATED.load(Ordering::SeqCst) > 0);
+///
+/// drop(db);
+///
+/// unsafe { hyperscan::reset_allocator() }.unwrap();
+/// ```
+pub trait Allocator {
+    /// Allocate a memory region of `size` bytes.
+    fn alloc(size: usize) -> *mut u8;
+
+    /// Free a memory region previously allocated by `alloc`.
+    ///
+    /// # Safety
+    ///
+    /// The `ptr` must be returned by `alloc` of the same allocator.
+    unsafe fn free(ptr: *mut u8);
+}
+
+type FreeFn = unsafe extern "C" fn(*mut c_void);
+
+unsafe extern "C" fn alloc_trampoline<A: Allocator>(size: usize) -> *mut c_void {
+    panic::catch_unwind(|| A::alloc(size)).unwrap_or(ptr::null_mut()) as *mut _
+}
+
+unsafe extern "C" fn free_trampoline<A: Allocator>(ptr: *mut c_void) {
+    if !ptr.is_null() {
+        let _ = panic::catch_unwind(|| A::free(ptr as *mut _));
+    }
+}
+
+/// The free function of the misc allocator, or `None` for `libc::free`.
+static MISC_FREE: RwLock<Option<FreeFn>> = RwLock::new(None);
+
+fn misc_free() -> Option<FreeFn> {
+    *MISC_FREE.read().unwrap_or_else(|err| err.into_inner())
+}
+
+fn set_misc_free(f: Option<FreeFn>) {
+    *MISC_FREE.write().unwrap_or_else(|err| err.into_inner()) = f;
+}
+
+/// Free the memory region allocated by the misc allocator, e.g. the info strings.
+pub(crate) unsafe fn free_misc(ptr: *mut c_void) {
+    match misc_free() {
+        Some(f) => f(ptr),
+        None => libc::free(ptr),
+    }
+}
+
+/// Returns `true` if the misc allocator was replaced.
+pub(crate) fn has_misc_allocator() -> bool {
+    misc_free().is_some()
+}
+
+/// Set the allocate and free functions used by Hyperscan for allocating
+/// memory at runtime for stream state, scratch space, database bytecode,
+/// and various other data structure returned by the Hyperscan API.
+///
+/// # Safety
+///
+/// No region allocated by the previous allocator may still be alive, and no other thread may use Hyperscan
+/// while the allocator is replaced, since a region must be freed by the allocator which allocated it.
+pub unsafe fn set_allocator<A: Allocator>() -> Result<()> {
+    ffi::hs_set_allocator(Some(alloc_trampoline::<A>), Some(free_trampoline::<A>)).ok()?;
+
+    set_misc_free(Some(free_trampoline::<A>));
+
+    Ok(())
+}
+
+/// Set the allocate and free functions used by Hyperscan for allocating memory for database bytecode
+/// produced by the compile calls and by database deserialization.
+///
+/// # Safety
+///
+/// No database allocated by the previous allocator may still be alive, see `set_allocator`.
+pub unsafe fn set_database_allocator<A: Allocator>() -> Result<()> {
+    ffi::hs_set_database_allocator(Some(alloc_trampoline::<A>), Some(free_trampoline::<A>)).ok()
+}
+
+/// Set the allocate and free functions used by Hyperscan for allocating memory for scratch space.
+///
+/// # Safety
+///
+/// No scratch space allocated by the previous allocator may still be alive, see `set_allocator`.
+pub unsafe fn set_scratch_allocator<A: Allocator>() -> Result<()> {
+    ffi::hs_set_scratch_allocator(Some(alloc_trampoline::<A>), Some(free_trampoline::<A>)).ok()
+}
+
+/// Set the allocate and free functions used by Hyperscan for allocating memory for stream state.
+///
+/// # Safety
+///
+/// No stream opened with the previous allocator may still be alive, see `set_allocator`.
+pub unsafe fn set_stream_allocator<A: Allocator>() -> Result<()> {
+    ffi::hs_set_stream_allocator(Some(alloc_trampoline::<A>), Some(free_trampoline::<A>)).ok()
+}
+
+/// Set the allocate and free functions used by Hyperscan for allocating memory for items
+/// returned by the Hyperscan API such as the compile errors, the database information and the serialized databases.
+///
+/// # Safety
+///
+/// No other thread may use Hyperscan while the allocator is replaced, see `set_allocator`.
+pub unsafe fn set_misc_allocator<A: Allocator>() -> Result<()> {
+    ffi::hs_set_misc_allocator(Some(alloc_trampoline::<A>), Some(free_trampoline::<A>)).ok()?;
+
+    set_misc_free(Some(free_trampoline::<A>));
+
+    Ok(())
+}
+
+/// Restore the system `malloc()` and `free()` for all the Hyperscan allocations.
+///
+/// # Safety
+///
+/// No region allocated by the replaced allocators may still be alive, see `set_allocator`.
+pub unsafe fn reset_allocator() -> Result<()> {
+    ffi::hs_set_allocator(None, None).ok()?;
+
+    set_misc_free(None);
+
+    Ok(())
+}
@@ -5,7 +5,7 @@ use std::mem::MaybeUninit;
 use anyhow::Result;
 use foreign_types::{foreign_type, ForeignTypeRef};
 
-use crate::common::{Block, Mode, Streaming, Vectored};
+use crate::common::{free_misc, Block, Mode, Streaming, Vectored};
 use crate::errors::AsResult;
 use crate::ffi;
 
@@ -61,7 +61,7 @@ impl<T> DatabaseRef<T> {
             ffi::hs_database_info(self.as_ptr(), p.as_mut_ptr()).and_then(|_| {
                 let p = p.assume_init();
                 let info = CStr::from_ptr(p).to_str()?.to_owned();
-                libc::free(p as *mut _);
+                free_misc(p as *mut _);
                 Ok(info)
             })
         }
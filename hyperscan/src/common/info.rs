@@ -0,0 +1,115 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error, Result};
+
+use crate::common::DatabaseRef;
+
+/// The information about a database, as reported by `DatabaseRef::info` or `SerializedDatabase::info`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::DatabaseInfo;
+/// let info: DatabaseInfo = "Version: 5.3.0 Features: AVX2 Mode: STREAM".parse().unwrap();
+///
+/// assert_eq!(info.version, semver::Version::new(5, 3, 0));
+/// assert_eq!(info.features, vec!["AVX2"]);
+/// assert_eq!(info.mode, "STREAM");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DatabaseInfo {
+    /// The version of Hyperscan which compiled the database.
+    pub version: semver::Version,
+    /// The CPU features the database was compiled for.
+    pub features: Vec<String>,
+    /// The mode the database was compiled for.
+    pub mode: String,
+}
+
+impl FromStr for DatabaseInfo {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let field = |name: &str, next: Option<&str>| -> Result<&str> {
+            let start = s
+                .find(name)
+                .map(|off| off + name.len())
+                .ok_or_else(|| anyhow!("missing `{}` in database info: {}", name, s))?;
+            let end = next
+                .and_then(|next| s[start..].find(next))
+                .map_or(s.len(), |off| start + off);
+
+            Ok(s[start..end].trim())
+        };
+
+        Ok(DatabaseInfo {
+            version: semver::Version::parse(field("Version:", Some("Features:"))?)?,
+            features: field("Features:", Some("Mode:"))?
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+            mode: field("Mode:", None)?.to_owned(),
+        })
+    }
+}
+
+impl<T> DatabaseRef<T> {
+    /// Provides the parsed information about a database.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"test"}.build().unwrap();
+    /// let info = db.database_info().unwrap();
+    ///
+    /// assert_eq!(info.mode, "STREAM");
+    /// ```
+    pub fn database_info(&self) -> Result<DatabaseInfo> {
+        self.info()?.parse()
+    }
+
+    /// Provides the version of Hyperscan which compiled the database.
+    pub fn version(&self) -> Result<semver::Version> {
+        self.database_info().map(|info| info.version)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::common::Serialized;
+    use crate::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_database_info() {
+        let info: DatabaseInfo = "Version: 5.3.0 Features:  Mode: BLOCK".parse().unwrap();
+
+        assert_eq!(info.version, semver::Version::new(5, 3, 0));
+        assert!(info.features.is_empty());
+        assert_eq!(info.mode, "BLOCK");
+
+        assert!("Version: 5.3.0".parse::<DatabaseInfo>().is_err());
+        assert!("Version: x Features: Mode: BLOCK".parse::<DatabaseInfo>().is_err());
+
+        let version = crate::version_str()
+            .to_str()
+            .unwrap()
+            .split_whitespace()
+            .next()
+            .unwrap();
+        let version = semver::Version::parse(version).unwrap();
+
+        let db: VectoredDatabase = pattern! {"test"}.build().unwrap();
+        let info = db.database_info().unwrap();
+
+        assert_eq!(info.version, version);
+        assert_eq!(info.mode, "VECTORED");
+        assert_eq!(db.version().unwrap(), version);
+
+        let buf = db.serialize().unwrap();
+
+        assert_eq!(buf.database_info().unwrap(), info);
+    }
+}
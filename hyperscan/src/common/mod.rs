@@ -1,8 +1,16 @@
+mod alloc;
 mod database;
+mod info;
 mod mode;
 mod serialized;
 
+pub(crate) use self::alloc::free_misc;
+pub use self::alloc::{
+    reset_allocator, set_allocator, set_database_allocator, set_misc_allocator, set_scratch_allocator,
+    set_stream_allocator, Allocator,
+};
 pub use self::database::{BlockDatabase, Database, DatabaseRef, StreamingDatabase, VectoredDatabase};
+pub use self::info::DatabaseInfo;
 pub use self::mode::{Block, Mode, Streaming, Vectored};
 pub use self::serialized::Serialized;
 
@@ -13,6 +21,9 @@ pub mod tests {
 
 use std::ffi::CStr;
 
+#[cfg(feature = "runtime")]
+use anyhow::{anyhow, Result};
+
 use crate::ffi;
 
 /// The current Hyperscan version information.
@@ -26,3 +37,15 @@ pub fn version() -> semver::Version {
 pub fn version_str() -> &'static CStr {
     unsafe { CStr::from_ptr(ffi::hs_version()) }
 }
+
+/// The version of the Hyperscan runtime actually linked, parsed from `version_str`.
+#[cfg(feature = "runtime")]
+pub(crate) fn runtime_version() -> Result<semver::Version> {
+    let s = version_str().to_str()?;
+    let version = s
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| anyhow!("missing version: {}", s))?;
+
+    Ok(semver::Version::parse(version)?)
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "compile")]
+use crate::compile::SomHorizon;
 use crate::ffi;
 
 /// Compile mode
@@ -21,6 +23,17 @@ pub trait Mode {
     fn is_streaming() -> bool {
         Self::ID == Streaming::ID
     }
+
+    /// The mode argument of the compiler, with the start of match horizon bits in the streaming mode.
+    #[cfg(feature = "compile")]
+    fn compile_mode(som: Option<SomHorizon>) -> u32 {
+        Self::ID
+            | if Self::is_streaming() {
+                som.map_or(0, |som| som as u32)
+            } else {
+                0
+            }
+    }
 }
 
 /// Block scan (non-streaming) database.
@@ -1,12 +1,13 @@
 use std::ffi::CStr;
 use std::fmt;
 use std::mem::MaybeUninit;
+use std::ptr;
 
 use anyhow::{Error, Result};
 use foreign_types::{ForeignType, ForeignTypeRef};
 use malloc_buf::Malloc;
 
-use crate::common::{Database, DatabaseRef};
+use crate::common::{alloc, free_misc, Database, DatabaseInfo, DatabaseRef};
 use crate::errors::AsResult;
 use crate::ffi;
 
@@ -21,6 +22,14 @@ pub trait Serialized {
     /// Providing information about a serialized database.
     fn info(&self) -> Result<String, Self::Error>;
 
+    /// Providing the parsed information about a serialized database.
+    fn database_info(&self) -> Result<DatabaseInfo, Self::Error>
+    where
+        Self::Error: From<Error>,
+    {
+        Ok(self.info()?.parse()?)
+    }
+
     /// Reconstruct a pattern database from a stream of bytes previously generated by `Database::serialize()`.
     fn deserialize<M>(&self) -> Result<Database<M>, Self::Error>;
 }
@@ -46,7 +55,7 @@ impl<T: AsRef<[u8]>> Serialized for T {
             ffi::hs_serialized_database_info(buf.as_ptr() as *const _, buf.len(), p.as_mut_ptr()).and_then(|_| {
                 let p = p.assume_init();
                 let info = CStr::from_ptr(p).to_str()?.to_owned();
-                libc::free(p as *mut _);
+                free_misc(p as *mut _);
                 Ok(info)
             })
         }
@@ -93,8 +102,27 @@ impl<T> DatabaseRef<T> {
         let mut size = MaybeUninit::uninit();
 
         unsafe {
-            ffi::hs_serialize_database(self.as_ptr(), ptr.as_mut_ptr(), size.as_mut_ptr())
-                .map(|_| Malloc::from_array(ptr.assume_init() as *mut u8, size.assume_init()))
+            ffi::hs_serialize_database(self.as_ptr(), ptr.as_mut_ptr(), size.as_mut_ptr()).and_then(|_| {
+                let (ptr, size) = (ptr.assume_init() as *mut u8, size.assume_init());
+
+                if !alloc::has_misc_allocator() {
+                    return Ok(Malloc::from_array(ptr, size));
+                }
+
+                // `Malloc` frees the buffer with `libc::free`, copy it out of the custom misc allocator.
+                let buf = libc::malloc(size) as *mut u8;
+
+                if buf.is_null() {
+                    free_misc(ptr as *mut _);
+
+                    return Err(crate::errors::Error::NoMem.into());
+                }
+
+                ptr::copy_nonoverlapping(ptr, buf, size);
+                free_misc(ptr as *mut _);
+
+                Ok(Malloc::from_array(buf, size))
+            })
         }
     }
 
@@ -1,13 +1,13 @@
 use std::ffi::CString;
 use std::mem::MaybeUninit;
-use std::ptr::null_mut;
+use std::ptr::{null, null_mut};
 use std::str::FromStr;
 
 use anyhow::Error;
 use foreign_types::{ForeignType, ForeignTypeRef};
 
 use crate::common::{Database, Mode};
-use crate::compile::{AsCompileResult, Flags, Pattern, Patterns, PlatformRef};
+use crate::compile::{AsCompileResult, ExprExt, Flags, Pattern, Patterns, PlatformRef};
 use crate::ffi;
 
 #[cfg(feature = "literal")]
@@ -25,6 +25,20 @@ pub trait Builder {
 
     /// Build an expression is compiled into a Hyperscan database for a target platform.
     fn for_platform<T: Mode>(&self, platform: Option<&PlatformRef>) -> Result<Database<T>, Self::Err>;
+
+    /// Build an expression is compiled into a Hyperscan database optimised for a target platform.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// # use hyperscan::{CpuFeatures, Platform, Tune};
+    /// let platform = Platform::new(Tune::SkylakeServer, CpuFeatures::AVX2);
+    /// let db: BlockDatabase = pattern! {"test"}.build_for(&platform).unwrap();
+    /// ```
+    fn build_for<T: Mode>(&self, platform: &PlatformRef) -> Result<Database<T>, Self::Err> {
+        self.for_platform(Some(platform))
+    }
 }
 
 /// Compile an expression into a Hyperscan database.
@@ -86,8 +100,12 @@ impl Builder for Pattern {
     /// into a Hyperscan database which can be passed to the runtime functions
     ///
     fn for_platform<T: Mode>(&self, platform: Option<&PlatformRef>) -> Result<Database<T>, Self::Err> {
+        if !self.ext.is_empty() {
+            return Patterns::from(vec![self.clone()]).for_platform(platform);
+        }
+
         let expr = CString::new(self.expression.as_bytes())?;
-        let mode = T::ID | if T::is_streaming() { self.som() } else { None }.map_or(0, |som| som as _);
+        let mode = T::compile_mode(self.som_mode());
         let mut db = MaybeUninit::uninit();
         let mut err = MaybeUninit::uninit();
 
@@ -135,21 +153,45 @@ impl Builder for Patterns {
             .enumerate()
             .map(|(i, Pattern { id, .. })| id.unwrap_or(i) as _)
             .collect::<Vec<_>>();
-        let mode = T::ID | if T::is_streaming() { self.som() } else { None }.map_or(0, |som| som as _);
+        let exts = self
+            .iter()
+            .map(|Pattern { ext, .. }| {
+                if ext.is_empty() {
+                    null()
+                } else {
+                    ext as *const ExprExt as *const ffi::hs_expr_ext_t
+                }
+            })
+            .collect::<Vec<_>>();
+        let mode = T::compile_mode(self.som_mode());
         let mut db = MaybeUninit::uninit();
         let mut err = MaybeUninit::uninit();
 
         unsafe {
-            ffi::hs_compile_multi(
-                ptrs.as_ptr(),
-                flags.as_ptr(),
-                ids.as_ptr(),
-                self.len() as u32,
-                mode,
-                platform.map_or_else(null_mut, ForeignTypeRef::as_ptr),
-                db.as_mut_ptr(),
-                err.as_mut_ptr(),
-            )
+            if exts.iter().all(|ext| ext.is_null()) {
+                ffi::hs_compile_multi(
+                    ptrs.as_ptr(),
+                    flags.as_ptr(),
+                    ids.as_ptr(),
+                    self.len() as u32,
+                    mode,
+                    platform.map_or_else(null_mut, ForeignTypeRef::as_ptr),
+                    db.as_mut_ptr(),
+                    err.as_mut_ptr(),
+                )
+            } else {
+                ffi::hs_compile_ext_multi(
+                    ptrs.as_ptr(),
+                    flags.as_ptr(),
+                    ids.as_ptr(),
+                    exts.as_ptr(),
+                    self.len() as u32,
+                    mode,
+                    platform.map_or_else(null_mut, ForeignTypeRef::as_ptr),
+                    db.as_mut_ptr(),
+                    err.as_mut_ptr(),
+                )
+            }
             .ok_or_else(|| err.assume_init())
             .map(|_| Database::from_ptr(db.assume_init()))
         }
@@ -167,7 +209,7 @@ impl Builder for Literal {
     /// into a Hyperscan database which can be passed to the runtime functions
     ///
     fn for_platform<T: Mode>(&self, platform: Option<&PlatformRef>) -> Result<Database<T>, Self::Err> {
-        let mode = T::ID | if T::is_streaming() { self.som() } else { None }.map_or(0, |som| som as _);
+        let mode = T::compile_mode(self.som_mode());
         let mut db = MaybeUninit::uninit();
         let mut err = MaybeUninit::uninit();
 
@@ -217,7 +259,7 @@ impl Builder for Literals {
             .enumerate()
             .map(|(i, Literal { id, .. })| id.unwrap_or(i) as _)
             .collect::<Vec<_>>();
-        let mode = T::ID | if T::is_streaming() { self.som() } else { None }.map_or(0, |som| som as _);
+        let mode = T::compile_mode(self.som_mode());
         let mut db = MaybeUninit::uninit();
         let mut err = MaybeUninit::uninit();
 
@@ -4,12 +4,12 @@ use std::mem::MaybeUninit;
 use std::ops::Deref;
 use std::str::FromStr;
 
-use anyhow::{anyhow, bail, Error, Result};
+use anyhow::{anyhow, bail, Context, Error, Result};
 use bitflags::bitflags;
 use derive_more::{From, Into};
 use foreign_types::{foreign_type, ForeignType, ForeignTypeRef};
 
-use crate::compile::{AsCompileResult, Pattern};
+use crate::compile::{AsCompileResult, Pattern, Patterns};
 use crate::ffi;
 
 bitflags! {
@@ -238,7 +238,7 @@ foreign_type! {
 }
 
 unsafe fn drop_expr_info(info: *mut ffi::hs_expr_info) {
-    libc::free(info as *mut _);
+    crate::common::free_misc(info as *mut _);
 }
 
 impl Deref for ExprInfoRef {
@@ -302,13 +302,22 @@ impl Pattern {
         let mut err = MaybeUninit::uninit();
 
         let info = unsafe {
-            ffi::hs_expression_ext_info(
-                expr.as_ptr() as *const i8,
-                self.flags.bits(),
-                &self.ext.0 as *const _,
-                info.as_mut_ptr(),
-                err.as_mut_ptr(),
-            )
+            if self.ext.is_empty() {
+                ffi::hs_expression_info(
+                    expr.as_ptr() as *const i8,
+                    self.flags.bits(),
+                    info.as_mut_ptr(),
+                    err.as_mut_ptr(),
+                )
+            } else {
+                ffi::hs_expression_ext_info(
+                    expr.as_ptr() as *const i8,
+                    self.flags.bits(),
+                    &self.ext.0 as *const _,
+                    info.as_mut_ptr(),
+                    err.as_mut_ptr(),
+                )
+            }
             .ok_or_else(|| err.assume_init())?;
 
             ExprInfo::from_ptr(info.assume_init())
@@ -317,3 +326,62 @@ impl Pattern {
         Ok(info)
     }
 }
+
+impl Patterns {
+    ///
+    /// Utility function providing information about each regular expression of the patterns.
+    ///
+    /// The patterns can be validated before building a database,
+    /// the error of an invalid pattern includes the index of the failing expression.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let patterns: Patterns = "/test/\n/foo(bar)?/i".parse().unwrap();
+    /// let infos = patterns.info().unwrap();
+    ///
+    /// assert_eq!(infos.iter().map(|info| info.min_width()).collect::<Vec<_>>(), vec![4, 3]);
+    /// assert_eq!(infos.iter().map(|info| info.max_width()).collect::<Vec<_>>(), vec![4, 6]);
+    ///
+    /// let patterns: Patterns = "/test/\n/foo(?=bar/".parse().unwrap();
+    /// let err = patterns.info().unwrap_err();
+    ///
+    /// assert!(err.to_string().starts_with("invalid expression #1"));
+    /// ```
+    pub fn info(&self) -> Result<Vec<ExprInfo>> {
+        self.iter()
+            .enumerate()
+            .map(|(i, pattern)| {
+                pattern
+                    .info()
+                    .with_context(|| format!("invalid expression #{}: {}", i, pattern))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_patterns_info() {
+        let patterns: Patterns = vec![pattern! {"test"}, pattern! {"foo(bar)?"; CASELESS}, pattern! {"a+$"}]
+            .into_iter()
+            .collect();
+
+        let infos = patterns.info().unwrap();
+
+        assert_eq!(infos.len(), 3);
+        assert_eq!((infos[0].min_width(), infos[0].max_width()), (4, 4));
+        assert_eq!((infos[1].min_width(), infos[1].max_width()), (3, 6));
+        assert!(infos[2].matches_at_eod());
+
+        let patterns: Patterns = vec![pattern! {"test"}, pattern! {"(foo"}].into_iter().collect();
+        let err = patterns.info().unwrap_err();
+
+        assert!(err.to_string().starts_with("invalid expression #1: (foo"));
+        assert!(err.downcast_ref::<crate::Error>().is_some());
+    }
+}
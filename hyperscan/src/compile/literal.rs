@@ -1,6 +1,6 @@
 use std::fmt;
 use std::iter::FromIterator;
-use std::str::FromStr;
+use std::str::{self, FromStr};
 
 use anyhow::{bail, Error, Result};
 use bitflags::bitflags;
@@ -34,6 +34,7 @@ impl FromStr for Flags {
                 'i' => flags |= Flags::CASELESS,
                 'm' => flags |= Flags::MULTILINE,
                 'H' => flags |= Flags::SINGLEMATCH,
+                'L' => flags |= Flags::SOM_LEFTMOST,
                 _ => {
                     bail!("invalid literal flag: {}", c);
                 }
@@ -55,15 +56,44 @@ impl fmt::Display for Flags {
         if self.contains(Flags::SINGLEMATCH) {
             write!(f, "H")?
         }
+        if self.contains(Flags::SOM_LEFTMOST) {
+            write!(f, "L")?
+        }
         Ok(())
     }
 }
 
 /// The pattern with pure literal expression.
+///
+/// The expression is matched byte for byte, it may contain any bytes, including NUL bytes,
+/// bytes which are not valid UTF-8 and regular expression metacharacters.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// let literals: Literals = vec![
+///     Literal::from_bytes(b"\0\xffa.b").unwrap().with_id(1),
+///     Literal::new("(foo)").unwrap().with_id(2).caseless(),
+/// ]
+/// .into_iter()
+/// .collect();
+///
+/// let db: BlockDatabase = literals.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut matches = vec![];
+///
+/// db.scan(&b"a.b \0\xffa.b (FOO)"[..], &s, |id, _, to, _| {
+///     matches.push((id, to));
+///     Matching::Continue
+/// }).unwrap();
+///
+/// assert_eq!(matches, vec![(1, 9), (2, 15)]);
+/// ```
 #[derive(Clone, Debug, PartialEq)]
 pub struct Literal {
-    /// The expression to parse.
-    pub expression: String,
+    /// The bytes to match.
+    pub expression: Vec<u8>,
     /// Flags which modify the behaviour of the expression.
     pub flags: Flags,
     /// ID number to be associated with the corresponding literal in the expressions array.
@@ -76,7 +106,7 @@ impl Literal {
     /// Construct a literal with expression.
     pub fn new<S: Into<String>>(expr: S) -> Result<Literal> {
         Ok(Literal {
-            expression: expr.into(),
+            expression: expr.into().into_bytes(),
             flags: Flags::empty(),
             id: None,
             som: None,
@@ -86,13 +116,49 @@ impl Literal {
     /// Construct a literal with expression and flags.
     pub fn with_flags<S: Into<String>>(expr: S, flags: Flags) -> Result<Literal> {
         Ok(Literal {
-            expression: expr.into(),
+            expression: expr.into().into_bytes(),
             flags,
             id: None,
             som: None,
         })
     }
 
+    /// Construct a literal from the bytes, which may contain NUL bytes and bytes which are not valid UTF-8.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Literal> {
+        Ok(Literal {
+            expression: bytes.as_ref().to_vec(),
+            flags: Flags::empty(),
+            id: None,
+            som: None,
+        })
+    }
+
+    /// Parse a literal like `FromStr`, unescaping the `\\` and `\xNN` escape sequences of the expression.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let literal = Literal::from_escaped(r"1:/a\x00b\\c.*\xff/i").unwrap();
+    ///
+    /// assert_eq!(literal.expression, b"a\0b\\c.*\xff");
+    /// assert_eq!(literal.flags, LiteralFlags::CASELESS);
+    /// assert_eq!(literal.id, Some(1));
+    /// ```
+    pub fn from_escaped(s: &str) -> Result<Literal> {
+        let mut literal: Literal = s.parse()?;
+
+        literal.expression = unescape(&literal.expression)?;
+
+        Ok(literal)
+    }
+
+    /// Set the ID number to be associated with the literal.
+    pub fn with_id(mut self, id: usize) -> Self {
+        self.id = Some(id);
+        self
+    }
+
     /// Set case-insensitive matching.
     pub fn caseless(mut self) -> Self {
         self.flags |= Flags::CASELESS;
@@ -117,7 +183,15 @@ impl Literal {
         self
     }
 
-    pub(crate) fn som(&self) -> Option<SomHorizon> {
+    /// Set the precision to track start of match offsets in stream state.
+    ///
+    /// It's only used by the streaming mode, when the literal reports the leftmost start of match offset.
+    pub fn som_horizon(mut self, som: SomHorizon) -> Self {
+        self.som = Some(som);
+        self
+    }
+
+    pub(crate) fn som_mode(&self) -> Option<SomHorizon> {
         if self.flags.contains(Flags::SOM_LEFTMOST) {
             self.som.or(Some(SomHorizon::Medium))
         } else {
@@ -126,6 +200,39 @@ impl Literal {
     }
 }
 
+/// Parse the literal bytes, unescaping the `\\` and `\xNN` escape sequences.
+fn unescape(s: &[u8]) -> Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(s.len());
+    let mut bytes = s.iter().copied();
+
+    while let Some(b) = bytes.next() {
+        if b != b'\\' {
+            buf.push(b);
+            continue;
+        }
+
+        match bytes.next() {
+            Some(b'\\') => buf.push(b'\\'),
+            Some(b'x') => {
+                let hex = bytes.by_ref().take(2).collect::<Vec<_>>();
+                let hex = str::from_utf8(&hex)?;
+
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) if hex.len() == 2 => buf.push(b),
+                    _ => bail!("invalid escape sequence: \\x{}", hex),
+                }
+            }
+            Some(c) => {
+                buf.push(b);
+                buf.push(c);
+            }
+            None => buf.push(b),
+        }
+    }
+
+    Ok(buf)
+}
+
 impl fmt::Display for Literal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Some(id) = self.id {
@@ -133,9 +240,9 @@ impl fmt::Display for Literal {
         }
 
         if self.id.is_some() || !self.flags.is_empty() {
-            write!(f, "/{}/", self.expression)?;
+            write!(f, "/{}/", Escaped(&self.expression))?;
         } else {
-            write!(f, "{}", self.expression)?;
+            write!(f, "{}", Escaped(&self.expression))?;
         }
 
         if !self.flags.is_empty() {
@@ -146,6 +253,33 @@ impl fmt::Display for Literal {
     }
 }
 
+/// Writes the bytes which are not valid UTF-8 as `\xNN` escape sequences, which `Literal::from_escaped` parses.
+struct Escaped<'a>(&'a [u8]);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = self.0;
+
+        loop {
+            match str::from_utf8(bytes) {
+                Ok(s) => return f.write_str(s),
+                Err(err) => {
+                    let (valid, rest) = bytes.split_at(err.valid_up_to());
+                    let (invalid, rest) = rest.split_at(err.error_len().unwrap_or_else(|| rest.len()));
+
+                    f.write_str(str::from_utf8(valid).map_err(|_| fmt::Error)?)?;
+
+                    for b in invalid {
+                        write!(f, "\\x{:02x}", b)?;
+                    }
+
+                    bytes = rest;
+                }
+            }
+        }
+    }
+}
+
 impl FromStr for Literal {
     type Err = Error;
 
@@ -208,7 +342,15 @@ impl FromStr for Literals {
 }
 
 impl Literals {
-    pub(crate) fn som(&self) -> Option<SomHorizon> {
+    /// Set the precision to track start of match offsets in stream state for all the literals.
+    pub fn som_horizon(mut self, som: SomHorizon) -> Self {
+        for literal in self.0.iter_mut() {
+            literal.som = Some(som);
+        }
+        self
+    }
+
+    pub(crate) fn som_mode(&self) -> Option<SomHorizon> {
         if self
             .iter()
             .any(|Literal { flags, .. }| flags.contains(Flags::SOM_LEFTMOST))
@@ -291,51 +433,96 @@ mod tests {
         let p: Literal = "test".parse().unwrap();
 
         assert_eq!(p, literal! { "test" });
-        assert_eq!(p.expression, "test");
+        assert_eq!(p.expression, b"test");
         assert!(p.flags.is_empty());
         assert_eq!(p.id, None);
 
         let p: Literal = "/test/".parse().unwrap();
 
         assert_eq!(p, literal! { "test" });
-        assert_eq!(p.expression, "test");
+        assert_eq!(p.expression, b"test");
         assert!(p.flags.is_empty());
         assert_eq!(p.id, None);
 
         let p: Literal = "/test/i".parse().unwrap();
 
         assert_eq!(p, literal! { "test"; CASELESS });
-        assert_eq!(p.expression, "test");
+        assert_eq!(p.expression, b"test");
         assert_eq!(p.flags, Flags::CASELESS);
         assert_eq!(p.id, None);
 
         let p: Literal = "3:/test/i".parse().unwrap();
 
         assert_eq!(p, literal! { 3 => "test"; CASELESS });
-        assert_eq!(p.expression, "test");
+        assert_eq!(p.expression, b"test");
         assert_eq!(p.flags, Flags::CASELESS);
         assert_eq!(p.id, Some(3));
 
         let p: Literal = "test/i".parse().unwrap();
 
         assert_eq!(p, literal! { "test/i" });
-        assert_eq!(p.expression, "test/i");
+        assert_eq!(p.expression, b"test/i");
         assert!(p.flags.is_empty());
         assert_eq!(p.id, None);
 
         let p: Literal = "/t/e/s/t/i".parse().unwrap();
 
         assert_eq!(p, literal! { "t/e/s/t"; CASELESS });
-        assert_eq!(p.expression, "t/e/s/t");
+        assert_eq!(p.expression, b"t/e/s/t");
         assert_eq!(p.flags, Flags::CASELESS);
         assert_eq!(p.id, None);
     }
 
+    #[test]
+    fn test_literal_escaped() {
+        let p: Literal = r"1:/a\x00b\\c.*/L".parse().unwrap();
+
+        assert_eq!(p.expression, br"a\x00b\\c.*");
+        assert_eq!(p.id, Some(1));
+
+        let p = Literal::from_escaped(r"1:/a\x00b\\c.*/L").unwrap();
+
+        assert_eq!(p.expression, b"a\0b\\c.*");
+        assert_eq!(p.flags, Flags::SOM_LEFTMOST);
+        assert_eq!(p.id, Some(1));
+
+        assert!(Literal::from_escaped(r"/a\x0/").is_err());
+
+        let p = Literal::from_escaped(r"/a\xff\x00\xfe/").unwrap();
+
+        assert_eq!(p.expression, b"a\xff\0\xfe");
+        assert_eq!(p.to_string(), "a\\xff\0\\xfe");
+    }
+
+    #[test]
+    fn test_literal_bytes() {
+        let p = Literal::from_bytes(b"\xff\x00\xfe").unwrap().with_id(7);
+
+        assert_eq!(p.expression, b"\xff\0\xfe");
+        assert_eq!(p.to_string(), "7:/\\xff\0\\xfe/");
+
+        let literals: Literals = vec![p, Literal::new("a.*b").unwrap().with_id(9).left_most()]
+            .into_iter()
+            .collect();
+
+        let db: BlockDatabase = literals.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan(&b"axb \xff\0\xfe a.*b"[..], &s, |id, from, to, _| {
+            matches.push((id, from, to));
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![(7, 0, 7), (9, 8, 12)]);
+    }
+
     #[test]
     fn test_pattern_build() {
         let p = &literal! {"test"};
 
-        assert_eq!(p.expression, "test");
+        assert_eq!(p.expression, b"test");
         assert!(p.flags.is_empty());
         assert_eq!(p.id, None);
 
@@ -348,7 +535,7 @@ mod tests {
     fn test_pattern_build_with_flags() {
         let p = &literal! {"test"; CASELESS};
 
-        assert_eq!(p.expression, "test");
+        assert_eq!(p.expression, b"test");
         assert_eq!(p.flags, Flags::CASELESS);
         assert_eq!(p.id, None);
 
@@ -7,6 +7,8 @@ mod expr;
 #[cfg(feature = "literal")]
 mod literal;
 mod platform;
+mod profile;
+mod retained;
 
 pub use self::builder::{compile, Builder};
 pub use self::error::{AsCompileResult, Error};
@@ -21,3 +23,5 @@ pub use self::expr::{ExprExt, ExprInfo};
 pub use self::literal::{Flags as LiteralFlags, Literal, Literals};
 pub use self::pattern::{Flags, Pattern, Patterns, SomHorizon};
 pub use self::platform::{CpuFeatures, Platform, PlatformRef, Tune};
+pub use self::profile::Profile;
+pub use self::retained::RetainedDatabase;
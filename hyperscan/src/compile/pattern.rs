@@ -1,8 +1,11 @@
 use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
 use std::iter::FromIterator;
+use std::path::Path;
 use std::str::FromStr;
 
-use anyhow::{bail, Error, Result};
+use anyhow::{bail, Context, Error, Result};
 use bitflags::bitflags;
 use derive_more::{Deref, DerefMut, From, Index, IndexMut, Into, IntoIterator};
 
@@ -173,6 +176,12 @@ impl Pattern {
         })
     }
 
+    /// Set the ID number to be associated with the pattern.
+    pub fn with_id(mut self, id: usize) -> Self {
+        self.id = Some(id);
+        self
+    }
+
     /// Set case-insensitive matching.
     pub fn caseless(mut self) -> Self {
         self.flags |= Flags::CASELESS;
@@ -227,6 +236,14 @@ impl Pattern {
         self
     }
 
+    /// Set the precision to track start of match offsets in stream state.
+    ///
+    /// It's only used by the streaming mode, when the expression reports the leftmost start of match offset.
+    pub fn som_horizon(mut self, som: SomHorizon) -> Self {
+        self.som = Some(som);
+        self
+    }
+
     /// Logical combination.
     #[cfg(feature = "v5")]
     pub fn combination(mut self) -> Self {
@@ -241,7 +258,37 @@ impl Pattern {
         self
     }
 
-    pub(crate) fn som(&self) -> Option<SomHorizon> {
+    /// Set the minimum end offset in the data stream at which this expression should match successfully.
+    pub fn min_offset(mut self, min_offset: u64) -> Self {
+        self.ext.set_min_offset(min_offset);
+        self
+    }
+
+    /// Set the maximum end offset in the data stream at which this expression should match successfully.
+    pub fn max_offset(mut self, max_offset: u64) -> Self {
+        self.ext.set_max_offset(max_offset);
+        self
+    }
+
+    /// Set the minimum match length (from start to end) required to successfully match this expression.
+    pub fn min_length(mut self, min_length: u64) -> Self {
+        self.ext.set_min_length(min_length);
+        self
+    }
+
+    /// Allow this expression to approximately match within the edit distance.
+    pub fn edit_distance(mut self, edit_distance: u32) -> Self {
+        self.ext.set_edit_distance(edit_distance);
+        self
+    }
+
+    /// Allow this expression to approximately match within the Hamming distance.
+    pub fn hamming_distance(mut self, hamming_distance: u32) -> Self {
+        self.ext.set_hamming_distance(hamming_distance);
+        self
+    }
+
+    pub(crate) fn som_mode(&self) -> Option<SomHorizon> {
         if self.flags.contains(Flags::SOM_LEFTMOST) {
             self.som.or(Some(SomHorizon::Medium))
         } else {
@@ -347,7 +394,76 @@ impl FromStr for Patterns {
 }
 
 impl Patterns {
-    pub(crate) fn som(&self) -> Option<SomHorizon> {
+    /// Load the patterns from a reader in the `id:/expression/flags` per-line format,
+    /// which is used by the Hyperscan tools like `hsbench`.
+    ///
+    /// The numeric ID is optional, the blank lines and the comment lines starting with `#` are skipped.
+    /// The error of an invalid line is reported with its line number.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let patterns = Patterns::from_reader(&b"# rules\n1:/foo/i\n\n2:/bar.*baz/sH"[..]).unwrap();
+    ///
+    /// assert_eq!(patterns.len(), 2);
+    /// assert_eq!(patterns[1].id, Some(2));
+    /// assert_eq!(patterns[1].flags, PatternFlags::DOTALL | PatternFlags::SINGLEMATCH);
+    ///
+    /// let err = Patterns::from_reader(&b"1:/foo/i\n2:/bar/x"[..]).unwrap_err();
+    ///
+    /// assert_eq!(err.to_string(), "line 2: 2:/bar/x");
+    /// ```
+    pub fn from_reader<R: Read>(reader: R) -> Result<Patterns> {
+        BufReader::new(reader)
+            .lines()
+            .enumerate()
+            .flat_map(|(i, line)| match line {
+                Ok(line) => {
+                    let trimmed = line.trim();
+
+                    if trimmed.is_empty() || trimmed.starts_with('#') {
+                        None
+                    } else {
+                        Some(parse_line(trimmed).with_context(|| format!("line {}: {}", i + 1, trimmed)))
+                    }
+                }
+                Err(err) => Some(Err(Error::new(err).context(format!("line {}", i + 1)))),
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Patterns)
+    }
+
+    /// Load the patterns from a file in the `id:/expression/flags` per-line format.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Patterns> {
+        let path = path.as_ref();
+
+        File::open(path)
+            .map_err(Error::new)
+            .and_then(Patterns::from_reader)
+            .with_context(|| format!("load patterns from {}", path.display()))
+    }
+
+    /// Set the precision to track start of match offsets in stream state for all the patterns.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// # use hyperscan::SomHorizon;
+    /// let db: StreamingDatabase = patterns!("foo", "bar"; SOM_LEFTMOST)
+    ///     .som_horizon(SomHorizon::Small)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn som_horizon(mut self, som: SomHorizon) -> Self {
+        for pattern in self.0.iter_mut() {
+            pattern.som = Some(som);
+        }
+        self
+    }
+
+    pub(crate) fn som_mode(&self) -> Option<SomHorizon> {
         if self
             .iter()
             .any(|Pattern { flags, .. }| flags.contains(Flags::SOM_LEFTMOST))
@@ -362,9 +478,42 @@ impl Patterns {
     }
 }
 
-/// Define `Pattern` with flags
+fn parse_line(line: &str) -> Result<Pattern> {
+    let expr = line.find(":/").map_or(line, |off| &line[off + 1..]);
+
+    if !expr.starts_with('/') || expr.rfind('/') == Some(0) {
+        bail!("expected `id:/expression/flags`");
+    }
+
+    line.parse()
+}
+
+/// Define `Pattern` with flags and extended parameters
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// let p = pattern! { "test"; CASELESS; edit_distance = 1, max_offset = 100 };
+///
+/// assert_eq!(p.to_string(), "/test/i{max_offset=100,edit_distance=1}");
+///
+/// let p = pattern! { 1 => "foobar"; ; min_offset = 10 };
+///
+/// assert_eq!(p.to_string(), "1:/foobar/{min_offset=10}");
+/// ```
 #[macro_export]
 macro_rules! pattern {
+    ( $expr:expr ; $( $flag:ident )|* ; $( $key:ident = $value:expr ),+ ) => {{
+        let pattern = pattern! { $expr ; $crate::CompileFlags::empty() $( | $crate::CompileFlags:: $flag )* };
+
+        pattern $( . $key ( $value ) )+
+    }};
+    ( $id:literal => $expr:expr ; $( $flag:ident )|* ; $( $key:ident = $value:expr ),+ ) => {{
+        let pattern = pattern! { $id => $expr ; $crate::CompileFlags::empty() $( | $crate::CompileFlags:: $flag )* };
+
+        pattern $( . $key ( $value ) )+
+    }};
     ( $expr:expr ) => {{
         pattern! { $expr ; $crate::CompileFlags::default() }
     }};
@@ -488,6 +637,38 @@ mod tests {
         assert_eq!(p.id, None);
     }
 
+    #[test]
+    fn test_pattern_builder() {
+        let p = Pattern::new("test")
+            .unwrap()
+            .caseless()
+            .dot_all()
+            .multi_line()
+            .left_most()
+            .with_id(1);
+
+        assert_eq!(
+            p,
+            pattern! { 1 => "test"; CASELESS | DOTALL | MULTILINE | SOM_LEFTMOST }
+        );
+
+        let p = Pattern::new("test")
+            .unwrap()
+            .single_match()
+            .allow_empty()
+            .utf8()
+            .ucp()
+            .prefilter();
+
+        assert_eq!(p.flags, "HV8WP".parse().unwrap());
+
+        #[cfg(feature = "v5")]
+        assert_eq!(
+            Pattern::new("1 & 2").unwrap().combination().quiet().flags,
+            Flags::COMBINATION | Flags::QUIET
+        );
+    }
+
     #[test]
     fn test_pattern_build() {
         let p = &pattern! {"test"};
@@ -529,10 +710,125 @@ mod tests {
         validate_database_with_size(&db, DATABASE_SIZE);
     }
 
+    #[cfg(feature = "runtime")]
+    #[test]
+    fn test_pattern_build_with_ext() {
+        let p = pattern! {"test"; SOM_LEFTMOST; min_offset = 8};
+
+        assert_eq!(p.ext.min_offset(), Some(8));
+
+        let db: BlockDatabase = p.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan("test foo test", &s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![9..13]);
+    }
+
+    #[cfg(feature = "runtime")]
+    #[test]
+    fn test_patterns_build_with_ext() {
+        let db: BlockDatabase = vec![
+            pattern! {0 => "test"; SOM_LEFTMOST},
+            pattern! {1 => "hatstand"; ; edit_distance = 1},
+        ]
+        .into_iter()
+        .collect::<Patterns>()
+        .build()
+        .unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan("test hatsland", &s, |id, _, to, _| {
+            matches.push((id, to));
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![(0, 4), (1, 13)]);
+    }
+
     #[test]
     fn test_patterns_build_with_flags() {
         let db: BlockDatabase = patterns!("test", "foo", "bar"; CASELESS | DOTALL).build().unwrap();
 
         validate_database_with_size(&db, DATABASE_SIZE);
     }
+
+    #[test]
+    fn test_patterns_som_horizon() {
+        use crate::common::{Block, Streaming};
+
+        #[cfg(feature = "runtime")]
+        {
+            let stream_size = |som| {
+                let db: StreamingDatabase = patterns!("foo.*bar", "a[^x]{100}b"; SOM_LEFTMOST)
+                    .som_horizon(som)
+                    .build()
+                    .unwrap();
+
+                db.stream_size().unwrap()
+            };
+
+            assert!(stream_size(SomHorizon::Small) < stream_size(SomHorizon::Large));
+        }
+
+        let p = pattern! {"test"; SOM_LEFTMOST}.som_horizon(SomHorizon::Small);
+
+        assert_eq!(p.som_mode(), Some(SomHorizon::Small));
+        assert_eq!(
+            Streaming::compile_mode(p.som_mode()),
+            ffi::HS_MODE_STREAM | ffi::HS_MODE_SOM_HORIZON_SMALL
+        );
+        assert_eq!(Block::compile_mode(p.som_mode()), ffi::HS_MODE_BLOCK);
+    }
+
+    #[test]
+    fn test_patterns_from_reader() {
+        let patterns = Patterns::from_reader(
+            &br#"
+# comment
+1:/foo/imsH8W
+
+  2:/a\/b:c/
+/bar/
+"#[..],
+        )
+        .unwrap();
+
+        assert_eq!(patterns.len(), 3);
+        assert_eq!(patterns[0].id, Some(1));
+        assert_eq!(patterns[0].expression, "foo");
+        assert_eq!(patterns[0].flags, "imsH8W".parse().unwrap());
+        assert_eq!(patterns[1].id, Some(2));
+        assert_eq!(patterns[1].expression, "a\\/b:c");
+        assert_eq!(patterns[2].id, None);
+
+        for (text, line) in &[
+            ("1:/foo/\n2:/bar/x", "line 2: 2:/bar/x"),
+            ("\n\nx:/foo/", "line 3: x:/foo/"),
+            ("1:/foo/\n2:foo", "line 2: 2:foo"),
+            ("/foo", "line 1: /foo"),
+        ] {
+            let err = Patterns::from_reader(text.as_bytes()).unwrap_err();
+
+            assert_eq!(&err.to_string(), line);
+        }
+
+        let path = std::env::temp_dir().join(format!("hyperscan-patterns-{}.txt", std::process::id()));
+
+        std::fs::write(&path, "1:/foo/i\n2:/bar/s\n").unwrap();
+
+        let patterns = Patterns::from_file(&path).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(patterns.len(), 2);
+        assert!(Patterns::from_file(&path).is_err());
+    }
 }
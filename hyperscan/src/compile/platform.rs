@@ -1,8 +1,9 @@
+use std::fmt;
 use std::mem::{self, MaybeUninit};
 
 use anyhow::Result;
 use bitflags::bitflags;
-use foreign_types::{foreign_type, ForeignType};
+use foreign_types::{foreign_type, ForeignType, ForeignTypeRef};
 
 use crate::errors::AsResult;
 use crate::ffi;
@@ -39,6 +40,23 @@ pub enum Tune {
     Goldmont = ffi::HS_TUNE_FAMILY_GLM,
 }
 
+impl Tune {
+    fn from_raw(tune: u32) -> Option<Tune> {
+        match tune {
+            ffi::HS_TUNE_FAMILY_GENERIC => Some(Tune::Generic),
+            ffi::HS_TUNE_FAMILY_SNB => Some(Tune::SandyBridge),
+            ffi::HS_TUNE_FAMILY_IVB => Some(Tune::IvyBridge),
+            ffi::HS_TUNE_FAMILY_HSW => Some(Tune::Haswell),
+            ffi::HS_TUNE_FAMILY_SLM => Some(Tune::Silvermont),
+            ffi::HS_TUNE_FAMILY_BDW => Some(Tune::Broadwell),
+            ffi::HS_TUNE_FAMILY_SKL => Some(Tune::Skylake),
+            ffi::HS_TUNE_FAMILY_SKX => Some(Tune::SkylakeServer),
+            ffi::HS_TUNE_FAMILY_GLM => Some(Tune::Goldmont),
+            _ => None,
+        }
+    }
+}
+
 impl Default for Tune {
     fn default() -> Self {
         Self::Generic
@@ -75,11 +93,24 @@ impl Platform {
     ///
     /// Hyperscan requires the Supplemental Streaming SIMD Extensions 3 instruction set.
     /// This function can be called on any x86 platform to determine
-    /// if the system provides the required instruction set.
+    /// if the system provides the required instruction set,
+    /// for example before deserializing a prebuilt database.
     ///
     /// This function does not test for more advanced features
     /// if Hyperscan has been built for a more specific architecture,
     /// for example the AVX2 instruction set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// # use hyperscan::{Platform, SerializedDatabase};
+    /// # let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+    /// # let buf = db.serialize().unwrap();
+    /// if Platform::is_valid().is_ok() {
+    ///     let db: BlockDatabase = buf.deserialize().unwrap();
+    /// }
+    /// ```
     pub fn is_valid() -> Result<()> {
         unsafe { ffi::hs_valid_platform().ok() }
     }
@@ -107,12 +138,75 @@ impl Platform {
     }
 }
 
+impl PlatformRef {
+    /// The tuning family of the target platform.
+    ///
+    /// Returns `None` if the tuning family is unknown to this version of the bindings.
+    pub fn tune(&self) -> Option<Tune> {
+        Tune::from_raw(unsafe { (*self.as_ptr()).tune })
+    }
+
+    /// The CPU features supported by the target platform.
+    pub fn cpu_features(&self) -> CpuFeatures {
+        CpuFeatures::from_bits_truncate(unsafe { (*self.as_ptr()).cpu_features })
+    }
+
+    /// Returns `true` if a database built for the target platform can run on the `host` platform.
+    pub fn is_compatible_with(&self, host: &PlatformRef) -> bool {
+        host.cpu_features().contains(self.cpu_features())
+    }
+}
+
+impl fmt::Debug for PlatformRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Platform")
+            .field("tune", &self.tune())
+            .field("cpu_features", &self.cpu_features())
+            .finish()
+    }
+}
+
+impl fmt::Debug for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
+    use crate::common::tests::*;
+    use crate::prelude::*;
+
     use super::*;
 
     #[test]
     pub fn test_platform() {
         assert!(Platform::is_valid().is_ok())
     }
+
+    #[test]
+    pub fn test_platform_info() {
+        let platform = Platform::new(Tune::SkylakeServer, CpuFeatures::AVX2 | CpuFeatures::AVX512);
+
+        assert_eq!(platform.tune(), Some(Tune::SkylakeServer));
+        assert_eq!(platform.cpu_features(), CpuFeatures::AVX2 | CpuFeatures::AVX512);
+
+        let generic = Platform::new(Tune::Generic, CpuFeatures::empty());
+
+        assert!(generic.is_compatible_with(&platform));
+        assert!(!platform.is_compatible_with(&generic));
+
+        let host = Platform::host().unwrap();
+
+        assert!(host.tune().is_some());
+        assert!(host.is_compatible_with(&host));
+    }
+
+    #[test]
+    pub fn test_build_for_platform() {
+        let platform = Platform::new(Tune::Haswell, CpuFeatures::AVX2);
+        let db: BlockDatabase = pattern! {"test"}.build_for(&platform).unwrap();
+
+        validate_database(&db);
+    }
 }
@@ -0,0 +1,183 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Error, Result};
+
+use crate::compile::{ExprExt, Flags, Pattern, Patterns, SomHorizon};
+
+/// Named presets which bundle the recommended flags and extended parameters for common workloads.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::Profile;
+/// let patterns: Patterns = "/password/\n/secret/m".parse().unwrap();
+/// let patterns = patterns.with_profile(Profile::Dlp);
+///
+/// assert!(patterns.iter().all(|p| p.flags.contains(CompileFlags::CASELESS | CompileFlags::SOM_LEFTMOST)));
+/// assert!(patterns.iter().all(|p| p.ext.min_length() == Some(4)));
+///
+/// let db: StreamingDatabase = patterns.build().unwrap();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Profile {
+    /// Intrusion detection, scanning binary network payloads.
+    ///
+    /// A `.` matches any byte, and each rule is reported at most once per block or stream,
+    /// without the cost of tracking the start of match.
+    Ids,
+    /// Data loss prevention, locating sensitive data in text.
+    ///
+    /// The matching is case-insensitive and Unicode aware,
+    /// and the start of match is tracked with full precision so the matched data can be redacted.
+    /// Matches shorter than 4 bytes are ignored, since they are too short to be sensitive data.
+    Dlp,
+    /// Line oriented log searching.
+    ///
+    /// The matching is case-insensitive, `^` and `$` anchor to the lines,
+    /// and the start of match is tracked with limited precision, which is enough for a line.
+    LogSearch,
+}
+
+impl FromStr for Profile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ids" => Ok(Profile::Ids),
+            "dlp" => Ok(Profile::Dlp),
+            "log-search" => Ok(Profile::LogSearch),
+            _ => bail!("unknown profile: {}", s),
+        }
+    }
+}
+
+impl Profile {
+    /// The flags recommended by the profile.
+    pub fn flags(&self) -> Flags {
+        match self {
+            Profile::Ids => Flags::DOTALL | Flags::SINGLEMATCH,
+            Profile::Dlp => Flags::CASELESS | Flags::UTF8 | Flags::UCP | Flags::SOM_LEFTMOST,
+            Profile::LogSearch => Flags::CASELESS | Flags::MULTILINE | Flags::SOM_LEFTMOST,
+        }
+    }
+
+    /// The precision to track start of match offsets in stream state recommended by the profile.
+    pub fn som_horizon(&self) -> Option<SomHorizon> {
+        match self {
+            Profile::Ids => None,
+            Profile::Dlp => Some(SomHorizon::Large),
+            Profile::LogSearch => Some(SomHorizon::Small),
+        }
+    }
+
+    /// The extended parameters recommended by the profile.
+    pub fn ext(&self) -> ExprExt {
+        let mut ext = ExprExt::default();
+
+        if let Profile::Dlp = self {
+            ext.set_min_length(4);
+        }
+
+        ext
+    }
+
+    /// Apply the profile to the pattern.
+    ///
+    /// The flags of the profile are added to the flags of the pattern,
+    /// the extended parameters of the profile are used when the pattern doesn't set them,
+    /// the flags which can't be used together are resolved as below:
+    ///
+    /// - `SINGLEMATCH` is removed when `SOM_LEFTMOST` is set, since they are not supported in conjunction.
+    /// - `UTF8` is added when `UCP` is set, since Unicode properties are only supported in UTF-8 mode.
+    pub fn apply(&self, pattern: &mut Pattern) {
+        pattern.flags |= self.flags();
+
+        if pattern.flags.contains(Flags::SOM_LEFTMOST) {
+            pattern.flags.remove(Flags::SINGLEMATCH);
+
+            if pattern.som.is_none() {
+                pattern.som = self.som_horizon();
+            }
+        }
+        if pattern.flags.contains(Flags::UCP) {
+            pattern.flags.insert(Flags::UTF8);
+        }
+
+        let ext = self.ext();
+
+        if let (None, Some(min_length)) = (pattern.ext.min_length(), ext.min_length()) {
+            pattern.ext.set_min_length(min_length);
+        }
+    }
+}
+
+impl Pattern {
+    /// Apply the flags of a profile to the pattern.
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        profile.apply(&mut self);
+        self
+    }
+}
+
+impl Patterns {
+    /// Apply the flags of a profile to all the patterns.
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.iter_mut().for_each(|pattern| profile.apply(pattern));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::tests::*;
+    use crate::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_profile() {
+        let p = pattern! {"test"}.with_profile(Profile::Ids);
+
+        assert_eq!(p.flags, Flags::DOTALL | Flags::SINGLEMATCH);
+        assert_eq!(p.som, None);
+
+        let p = pattern! {"test"; SOM_LEFTMOST}.with_profile(Profile::Ids);
+
+        assert_eq!(p.flags, Flags::DOTALL | Flags::SOM_LEFTMOST);
+        assert_eq!(p.som, None);
+
+        let p = pattern! {"test"; SINGLEMATCH}.with_profile(Profile::Dlp);
+
+        assert_eq!(
+            p.flags,
+            Flags::CASELESS | Flags::UTF8 | Flags::UCP | Flags::SOM_LEFTMOST
+        );
+        assert_eq!(p.som, Some(SomHorizon::Large));
+        assert_eq!(p.ext.min_length(), Some(4));
+
+        let p = pattern! {"test"}.min_length(8).with_profile(Profile::Dlp);
+
+        assert_eq!(p.ext.min_length(), Some(8));
+
+        let p = pattern! {"test"; UCP}.with_profile(Profile::LogSearch);
+
+        assert!(p.flags.contains(Flags::UTF8));
+        assert_eq!(p.som, Some(SomHorizon::Small));
+        assert!(p.ext.is_empty());
+
+        assert_eq!("log-search".parse::<Profile>().unwrap(), Profile::LogSearch);
+        assert!("unknown".parse::<Profile>().is_err());
+    }
+
+    #[test]
+    fn test_profile_build() {
+        for &profile in &[Profile::Ids, Profile::Dlp, Profile::LogSearch] {
+            let patterns: Patterns = "/test/\n/foo(bar)?/H".parse().unwrap();
+
+            let db: StreamingDatabase = patterns.with_profile(profile).build().unwrap();
+
+            validate_database(&db);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use anyhow::{Error, Result};
+
+use crate::common::{Database, DatabaseRef, Mode};
+use crate::compile::{Builder, Patterns, PlatformRef};
+
+/// A pattern database which retains the patterns it was compiled from.
+///
+/// The retained patterns allow the same pattern set to be rebuilt for a different mode,
+/// without threading the original pattern source through the application.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::{BlockMode, RetainedDatabase, StreamingMode};
+/// let db: RetainedDatabase<BlockMode> = "/test/\n/foo/i".parse().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+///
+/// db.scan("test", &s, Matching::Continue).unwrap();
+///
+/// let db: RetainedDatabase<StreamingMode> = db.recompile_as().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let st = db.open_stream().unwrap();
+/// let mut matches = vec![];
+///
+/// st.scan("te", &s, |id, _, to, _| {
+///     matches.push((id, to));
+///     Matching::Continue
+/// }).unwrap();
+/// st.scan("st FOO", &s, |id, _, to, _| {
+///     matches.push((id, to));
+///     Matching::Continue
+/// }).unwrap();
+/// st.close(&s, Matching::Continue).unwrap();
+///
+/// assert_eq!(matches, vec![(0, 4), (1, 8)]);
+/// ```
+pub struct RetainedDatabase<T> {
+    db: Database<T>,
+    patterns: Patterns,
+}
+
+impl<T> fmt::Debug for RetainedDatabase<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetainedDatabase")
+            .field("patterns", &self.patterns)
+            .finish()
+    }
+}
+
+impl<T> Deref for RetainedDatabase<T> {
+    type Target = DatabaseRef<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.db
+    }
+}
+
+impl<T: Mode> FromStr for RetainedDatabase<T> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::compile(s.parse()?)
+    }
+}
+
+impl<T: Mode> RetainedDatabase<T> {
+    /// Compile the patterns into a database which retains them.
+    pub fn compile(patterns: Patterns) -> Result<Self> {
+        Self::for_platform(patterns, None)
+    }
+
+    /// Compile the patterns into a database which retains them for a target platform.
+    pub fn for_platform(patterns: Patterns, platform: Option<&PlatformRef>) -> Result<Self> {
+        let db = patterns.for_platform(platform)?;
+
+        Ok(RetainedDatabase { db, patterns })
+    }
+
+    /// Rebuild the retained patterns as a database of a different mode.
+    pub fn recompile_as<U: Mode>(&self) -> Result<RetainedDatabase<U>> {
+        RetainedDatabase::compile(self.patterns.clone())
+    }
+
+    /// Rebuild the retained patterns as a database of a different mode for a target platform.
+    pub fn recompile_for_platform<U: Mode>(&self, platform: Option<&PlatformRef>) -> Result<RetainedDatabase<U>> {
+        RetainedDatabase::for_platform(self.patterns.clone(), platform)
+    }
+}
+
+impl<T> RetainedDatabase<T> {
+    /// The patterns which the database was compiled from.
+    pub fn patterns(&self) -> &Patterns {
+        &self.patterns
+    }
+
+    /// Consumes the wrapper, returning the database and the patterns.
+    pub fn into_parts(self) -> (Database<T>, Patterns) {
+        (self.db, self.patterns)
+    }
+
+    /// Consumes the wrapper, returning the database.
+    pub fn into_inner(self) -> Database<T> {
+        self.db
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::tests::*;
+    use crate::common::{Block as BlockMode, Streaming as StreamingMode, Vectored as VectoredMode};
+
+    use super::*;
+
+    #[test]
+    fn test_recompile_as() {
+        let db: RetainedDatabase<BlockMode> = "/test/\n/foo/i".parse().unwrap();
+
+        validate_database(&db);
+        assert_eq!(db.name(), "Block");
+        assert_eq!(db.patterns().len(), 2);
+
+        let db = db.recompile_as::<VectoredMode>().unwrap();
+
+        validate_database(&db);
+        assert_eq!(db.name(), "Vectored");
+
+        let db = db.recompile_as::<StreamingMode>().unwrap();
+
+        validate_database(&db);
+        assert_eq!(db.name(), "Streaming");
+        #[cfg(feature = "runtime")]
+        assert!(db.stream_size().unwrap() > 0);
+
+        let (db, patterns) = db.into_parts();
+
+        assert_eq!(db.name(), "Streaming");
+        assert_eq!(patterns.len(), 2);
+    }
+}
@@ -58,6 +58,14 @@ pub enum Error {
     #[error("Provided buffer was too small.")]
     InsufficientSpace,
 
+    /// The scratch space was not allocated (or grown) for the database.
+    #[error("The scratch space was not allocated (or grown) for the database.")]
+    ScratchMismatch,
+
+    /// The expression was not compiled with `SOM_LEFTMOST`, the start of match offsets are unavailable.
+    #[error("The expression was not compiled with `SOM_LEFTMOST`, the start of match offsets are unavailable.")]
+    SomRequired,
+
     /// Unexpected internal error.
     #[cfg(feature = "v5")]
     #[error("Unexpected internal error.")]
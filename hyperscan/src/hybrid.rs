@@ -0,0 +1,266 @@
+//! Graceful degradation for pattern sets which Hyperscan can only partially compile.
+//!
+//! A hybrid database compiles every pattern accepted by Hyperscan into a block database,
+//! and runs the remaining patterns through the pure-Rust `regex` crate over the same input.
+//! Matches from both engines are merged and reported in order of their end offsets.
+//!
+//! Note: the fallback patterns follow the leftmost-first semantics of the `regex` crate,
+//! so they report one match per non-overlapping occurrence rather than every end offset.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use hyperscan::prelude::*;
+//! # use hyperscan::hybrid;
+//! let patterns: Patterns = vec![
+//!     pattern! {"test"; SOM_LEFTMOST},
+//!     // Hyperscan rejects patterns which can match an empty buffer.
+//!     pattern! {r"^\d*"; SOM_LEFTMOST},
+//! ]
+//! .into_iter()
+//! .collect();
+//!
+//! let db = hybrid::Database::compile(&patterns).unwrap();
+//! assert_eq!(db.fallbacks().collect::<Vec<_>>(), vec![1]);
+//!
+//! let s = db.alloc_scratch().unwrap();
+//! let mut matches = vec![];
+//!
+//! db.scan("2020 test data", &s, |id, from, to, _| {
+//!     matches.push((id, from, to));
+//!     Matching::Continue
+//! }).unwrap();
+//!
+//! assert_eq!(matches, vec![(1, 0, 4), (0, 5, 9)]);
+//! ```
+use anyhow::{bail, Result};
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::common::BlockDatabase;
+use crate::compile::{Builder, ExprExt, Flags, Pattern, Patterns};
+use crate::errors::Error;
+use crate::runtime::{self, Matching};
+
+/// A pattern database which falls back to the `regex` crate for unsupported patterns.
+pub struct Database {
+    db: Option<BlockDatabase>,
+    fallbacks: Vec<Fallback>,
+}
+
+/// A scratch space for use by the hybrid database.
+pub struct Scratch(Option<runtime::Scratch>);
+
+struct Fallback {
+    id: u32,
+    regex: Regex,
+    flags: Flags,
+    ext: ExprExt,
+}
+
+impl Fallback {
+    fn new(id: u32, pattern: &Pattern) -> Result<Fallback> {
+        let unsupported = pattern.flags & (Flags::PREFILTER | Flags::COMBINATION);
+
+        if !unsupported.is_empty() {
+            bail!("pattern #{} can't fall back with flags `{}`", id, unsupported);
+        }
+        if pattern.ext.edit_distance().is_some() || pattern.ext.hamming_distance().is_some() {
+            bail!("pattern #{} can't fall back with approximate matching", id);
+        }
+
+        let regex = RegexBuilder::new(&pattern.expression)
+            .case_insensitive(pattern.flags.contains(Flags::CASELESS))
+            .multi_line(pattern.flags.contains(Flags::MULTILINE))
+            .dot_matches_new_line(pattern.flags.contains(Flags::DOTALL))
+            .unicode(pattern.flags.intersects(Flags::UTF8 | Flags::UCP))
+            .build()?;
+
+        Ok(Fallback {
+            id,
+            regex,
+            flags: pattern.flags,
+            ext: pattern.ext,
+        })
+    }
+
+    fn accepts(&self, from: u64, to: u64) -> bool {
+        (from != to || self.flags.contains(Flags::ALLOWEMPTY))
+            && self.ext.min_offset().map_or(true, |off| to >= off)
+            && self.ext.max_offset().map_or(true, |off| to <= off)
+            && self.ext.min_length().map_or(true, |len| to - from >= len)
+    }
+
+    fn find_iter<'a>(&'a self, data: &'a [u8]) -> impl Iterator<Item = (u32, u64, u64)> + 'a {
+        let som = self.flags.contains(Flags::SOM_LEFTMOST);
+        let limit = if self.flags.contains(Flags::SINGLEMATCH) {
+            1
+        } else {
+            usize::max_value()
+        };
+
+        self.regex
+            .find_iter(data)
+            .map(|m| (m.start() as u64, m.end() as u64))
+            .filter(move |&(from, to)| self.accepts(from, to))
+            .map(move |(from, to)| (self.id, if som { from } else { 0 }, to))
+            .take(limit)
+    }
+}
+
+fn failed_expression(err: &anyhow::Error) -> Option<usize> {
+    match err.downcast_ref::<Error>() {
+        Some(Error::CompileError(err)) => err.expression(),
+        _ => None,
+    }
+}
+
+impl Database {
+    /// Compile a set of patterns, falling back to the `regex` crate for
+    /// every pattern which is rejected by the Hyperscan compiler.
+    ///
+    /// An error is returned if a rejected pattern can't be handled by the `regex` crate either.
+    pub fn compile(patterns: &Patterns) -> Result<Database> {
+        let mut native = patterns
+            .iter()
+            .enumerate()
+            .map(|(i, pattern)| Pattern {
+                id: Some(pattern.id.unwrap_or(i)),
+                ..pattern.clone()
+            })
+            .collect::<Vec<_>>();
+        let mut fallbacks = vec![];
+
+        let db = loop {
+            if native.is_empty() {
+                break None;
+            }
+
+            match native.iter().cloned().collect::<Patterns>().build() {
+                Ok(db) => break Some(db),
+                Err(err) => match failed_expression(&err) {
+                    Some(n) if n < native.len() => {
+                        let pattern = native.remove(n);
+
+                        fallbacks.push(Fallback::new(pattern.id.unwrap() as u32, &pattern)?);
+                    }
+                    _ => return Err(err),
+                },
+            }
+        };
+
+        Ok(Database { db, fallbacks })
+    }
+
+    /// Returns the IDs of the patterns which fell back to the `regex` crate.
+    pub fn fallbacks(&self) -> impl Iterator<Item = u32> + '_ {
+        self.fallbacks.iter().map(|fallback| fallback.id)
+    }
+
+    /// Returns the Hyperscan database of the supported patterns, if any.
+    pub fn database(&self) -> Option<&BlockDatabase> {
+        self.db.as_ref()
+    }
+
+    /// Allocate a "scratch" space for use by the hybrid database.
+    pub fn alloc_scratch(&self) -> Result<Scratch> {
+        self.db.as_ref().map(|db| db.alloc_scratch()).transpose().map(Scratch)
+    }
+
+    /// The block (non-streaming) regular expression scanner.
+    ///
+    /// Matches of the Hyperscan database and the fallback patterns are merged in order of their end offsets.
+    pub fn scan<T, F>(&self, data: T, scratch: &Scratch, mut on_match_event: F) -> Result<()>
+    where
+        T: AsRef<[u8]>,
+        F: FnMut(u32, u64, u64, u32) -> Matching,
+    {
+        let data = data.as_ref();
+
+        let mut pending = self
+            .fallbacks
+            .iter()
+            .filter(|fallback| !fallback.flags.contains(Flags::QUIET))
+            .flat_map(|fallback| fallback.find_iter(data))
+            .collect::<Vec<_>>();
+
+        pending.sort_by_key(|&(id, _, to)| (to, id));
+
+        let mut pending = pending.into_iter().peekable();
+
+        if let (Some(db), Some(s)) = (self.db.as_ref(), scratch.0.as_ref()) {
+            db.scan(data, s, |id, from, to, flags| {
+                while let Some(&(fallback_id, fallback_from, fallback_to)) = pending.peek() {
+                    if fallback_to > to {
+                        break;
+                    }
+
+                    pending.next();
+
+                    if on_match_event(fallback_id, fallback_from, fallback_to, 0) == Matching::Terminate {
+                        return Matching::Terminate;
+                    }
+                }
+
+                on_match_event(id, from, to, flags)
+            })?;
+        }
+
+        for (id, from, to) in pending {
+            if on_match_event(id, from, to, 0) == Matching::Terminate {
+                return Err(Error::ScanTerminated.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hybrid_database() {
+        let patterns: Patterns = vec![
+            pattern! {"test"; SOM_LEFTMOST},
+            pattern! {r"^\d*"; SOM_LEFTMOST},
+            pattern! {"data"},
+        ]
+        .into_iter()
+        .collect();
+
+        let db = Database::compile(&patterns).unwrap();
+
+        assert!(db.database().is_some());
+        assert_eq!(db.fallbacks().collect::<Vec<_>>(), vec![1]);
+
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan("2020 test data", &s, |id, from, to, _| {
+            matches.push((id, from, to));
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![(1, 0, 4), (0, 5, 9), (2, 0, 14)]);
+    }
+
+    #[test]
+    fn test_hybrid_database_terminate() {
+        let patterns: Patterns = vec![pattern! {r"^\d*"}, pattern! {"test"}].into_iter().collect();
+
+        let db = Database::compile(&patterns).unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        assert!(db
+            .scan("2020 test data", &s, |id, from, to, _| {
+                matches.push((id, from, to));
+                Matching::Terminate
+            })
+            .is_err());
+
+        assert_eq!(matches, vec![(0, 0, 4)]);
+    }
+}
@@ -48,6 +48,8 @@ mod errors;
 mod compile;
 #[cfg(feature = "chimera")]
 pub mod chimera;
+#[cfg(feature = "hybrid")]
+pub mod hybrid;
 #[cfg(all(feature = "compile", feature = "runtime"))]
 pub mod regex;
 #[cfg(feature = "runtime")]
@@ -66,9 +68,10 @@ pub use crate::common::Streaming;
 #[deprecated = "use `VectoredMode` instead"]
 pub use crate::common::Vectored;
 pub use crate::common::{
-    version, version_str, Block as BlockMode, BlockDatabase, Database, DatabaseRef, Mode,
-    Serialized as SerializedDatabase, Streaming as StreamingMode, StreamingDatabase, Vectored as VectoredMode,
-    VectoredDatabase,
+    reset_allocator, set_allocator, set_database_allocator, set_misc_allocator, set_scratch_allocator,
+    set_stream_allocator, version, version_str, Allocator, Block as BlockMode, BlockDatabase, Database, DatabaseInfo,
+    DatabaseRef, Mode, Serialized as SerializedDatabase, Streaming as StreamingMode, StreamingDatabase,
+    Vectored as VectoredMode, VectoredDatabase,
 };
 #[doc(hidden)]
 #[deprecated = "use `Error` instead"]
@@ -88,15 +91,31 @@ cfg_if::cfg_if! {
         pub use crate::compile::Flags as CompileFlags;
         pub use crate::compile::{
             compile, Builder as DatabaseBuilder, Builder, CpuFeatures, Error as CompileError, ExprExt, ExprInfo,
-            Flags as PatternFlags, Pattern, Patterns, Platform, PlatformRef, SomHorizon, Tune,
+            Flags as PatternFlags, Pattern, Patterns, Platform, PlatformRef, Profile, RetainedDatabase, SomHorizon,
+            Tune,
         };
         #[cfg(feature = "literal")]
         pub use crate::compile::{Literal, LiteralFlags, Literals};
     }
 }
 
+#[cfg(feature = "async")]
+pub use crate::runtime::MatchStream;
+#[cfg(all(feature = "runtime", feature = "arrayvec"))]
+pub use crate::runtime::MatchesN;
+#[cfg(feature = "record")]
+pub use crate::runtime::{
+    CaptureReader, Record, RecordKind, RecordedStream, Recorder, ReplayDiff, ReplayReport, Replayer,
+};
 #[cfg(feature = "runtime")]
-pub use crate::runtime::{MatchEventHandler, Matching, Scratch, ScratchRef, Stream, StreamRef};
+pub use crate::runtime::{
+    Counters, Match, MatchEventHandler, Matching, RuleGroups, Scanner, Schedule, Scratch, ScratchRef, ShutdownReport,
+    Stream, StreamMap, StreamRef, StreamWriter, VectoredBlock, VectoredBuffer, VectoredScannable,
+};
+#[cfg(feature = "histogram")]
+pub use crate::runtime::{LatencyHistogram, LatencySnapshot};
+#[cfg(feature = "mmap")]
+pub use crate::runtime::{Segment, SegmentMatch, Segments};
 
 /// The `hyperscan` Prelude
 pub mod prelude {
@@ -0,0 +1,221 @@
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+
+use crate::common::Block;
+use crate::compile::{Builder, Flags, Pattern, Patterns};
+use crate::errors::Error;
+use crate::runtime::{Matching, Scanner};
+
+/// How a `Matcher` handles the patterns compiled without `SOM_LEFTMOST`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SomFallback {
+    /// Fail with `Error::SomRequired` when the matcher is built.
+    Error,
+    /// Report only the end offset of the matches, whose start is unknown.
+    EndOnly,
+}
+
+impl Default for SomFallback {
+    fn default() -> Self {
+        SomFallback::Error
+    }
+}
+
+/// A match of a pattern in the text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextMatch<'t> {
+    /// The ID number of the pattern that matched.
+    pub id: u32,
+    text: &'t [u8],
+    start: Option<usize>,
+    end: usize,
+}
+
+impl<'t> TextMatch<'t> {
+    /// The starting byte offset of the match, or `None` if the pattern was compiled without `SOM_LEFTMOST`.
+    pub fn start(&self) -> Option<usize> {
+        self.start
+    }
+
+    /// The ending byte offset of the match.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// The matched text, or `None` if the pattern was compiled without `SOM_LEFTMOST`.
+    pub fn as_bytes(&self) -> Option<&'t [u8]> {
+        self.start.map(|start| &self.text[start..self.end])
+    }
+}
+
+/// A block mode matcher which extracts the matched text or replaces it.
+///
+/// The patterns compiled without `SOM_LEFTMOST` report `from = 0` for every match,
+/// so the matcher knows which patterns have the start of match information,
+/// and rejects the others unless `SomFallback::EndOnly` is explicitly allowed.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::regex::{Matcher, SomFallback};
+/// # use hyperscan::Error;
+/// let patterns: Patterns = "/\\d+/L\n/foo/L".parse().unwrap();
+/// let matcher = Matcher::new(patterns, SomFallback::Error).unwrap();
+///
+/// assert_eq!(matcher.replace_all(b"foo 123 bar", |_| "x").unwrap(), &b"x x bar"[..]);
+///
+/// let err = Matcher::new("/\\d+/".parse().unwrap(), SomFallback::Error).err().unwrap();
+///
+/// assert_eq!(err.downcast_ref::<Error>(), Some(&Error::SomRequired));
+/// ```
+pub struct Matcher {
+    scanner: Scanner<Block>,
+    som: HashSet<u32>,
+    end_only: Vec<u32>,
+}
+
+impl Matcher {
+    /// Compile the patterns, checking which of them have the start of match information.
+    pub fn new(patterns: Patterns, fallback: SomFallback) -> Result<Matcher> {
+        let mut som = HashSet::new();
+        let mut end_only = vec![];
+
+        for (i, Pattern { id, flags, .. }) in patterns.iter().enumerate() {
+            let id = id.unwrap_or(i) as u32;
+
+            if flags.contains(Flags::SOM_LEFTMOST) {
+                som.insert(id);
+            } else {
+                end_only.push(id);
+            }
+        }
+
+        if !end_only.is_empty() && fallback == SomFallback::Error {
+            return Err(Error::SomRequired).with_context(|| {
+                format!(
+                    "patterns {:?} must be compiled with `SOM_LEFTMOST`, or allow `SomFallback::EndOnly`",
+                    end_only
+                )
+            });
+        }
+
+        Ok(Matcher {
+            scanner: Scanner::new(patterns.build()?)?,
+            som,
+            end_only,
+        })
+    }
+
+    /// Returns `true` if all the patterns have the start of match information.
+    pub fn has_som(&self) -> bool {
+        self.end_only.is_empty()
+    }
+
+    /// Returns all the matches in the text, ordered by their end offsets.
+    ///
+    /// Every match of a pattern is reported, including the overlapping ones.
+    pub fn find_all<'t>(&self, text: &'t [u8]) -> Result<Vec<TextMatch<'t>>> {
+        let mut matches = vec![];
+
+        self.scanner.scan(text, |id, from, to, _| {
+            matches.push(TextMatch {
+                id,
+                text,
+                start: if self.som.contains(&id) {
+                    Some(from as usize)
+                } else {
+                    None
+                },
+                end: to as usize,
+            });
+
+            Matching::Continue
+        })?;
+
+        Ok(matches)
+    }
+
+    /// Replace the leftmost-longest non-overlapping matches with the replacements.
+    ///
+    /// The replacement of a match without the start of match information is inserted at its end offset.
+    pub fn replace_all<'t, F, R>(&self, text: &'t [u8], mut rep: F) -> Result<Cow<'t, [u8]>>
+    where
+        F: FnMut(&TextMatch<'t>) -> R,
+        R: AsRef<[u8]>,
+    {
+        let mut matches = self.find_all(text)?;
+
+        if matches.is_empty() {
+            return Ok(Cow::Borrowed(text));
+        }
+
+        matches.sort_by_key(|m| (m.start.unwrap_or(m.end), m.start.is_some(), Reverse(m.end)));
+
+        let mut replaced = Vec::with_capacity(text.len());
+        let mut last = 0;
+
+        for m in &matches {
+            let start = m.start.unwrap_or(m.end);
+
+            if start < last {
+                continue;
+            }
+
+            replaced.extend_from_slice(&text[last..start]);
+            replaced.extend_from_slice(rep(m).as_ref());
+            last = m.end;
+        }
+
+        replaced.extend_from_slice(&text[last..]);
+
+        Ok(Cow::Owned(replaced))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matcher() {
+        let matcher = Matcher::new("/a+/L\n/b/L".parse().unwrap(), SomFallback::Error).unwrap();
+        let text = b"baaab";
+
+        assert!(matcher.has_som());
+        assert_eq!(
+            matcher
+                .find_all(text)
+                .unwrap()
+                .iter()
+                .map(|m| m.as_bytes().unwrap())
+                .collect::<Vec<_>>(),
+            vec![&b"b"[..], b"a", b"aa", b"aaa", b"b"]
+        );
+        assert_eq!(matcher.replace_all(text, |m| m.id.to_string()).unwrap(), &b"101"[..]);
+        assert_eq!(matcher.replace_all(b"xyz", |_| "").unwrap(), Cow::Borrowed(&b"xyz"[..]));
+    }
+
+    #[test]
+    fn test_matcher_without_som() {
+        let err = Matcher::new("/a+/L\n/b/".parse().unwrap(), SomFallback::Error)
+            .err()
+            .unwrap();
+
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::SomRequired));
+        assert!(err.to_string().contains("[1]"));
+
+        let matcher = Matcher::new("/a+/L\n/b/".parse().unwrap(), SomFallback::EndOnly).unwrap();
+        let matches = matcher.find_all(b"baab").unwrap();
+
+        assert!(!matcher.has_som());
+        assert_eq!(matches[0].start(), None);
+        assert_eq!(matches[0].end(), 1);
+        assert_eq!(matches[0].as_bytes(), None);
+        assert_eq!(matches[1].as_bytes(), Some(&b"a"[..]));
+        assert_eq!(matcher.replace_all(b"baab", |_| "-").unwrap(), &b"b--b-"[..]);
+    }
+}
@@ -1,6 +1,14 @@
 //! Regex compatible interface
 mod builder;
+mod matcher;
+#[cfg(feature = "prefilter")]
+mod prefilter;
 mod re;
+mod set;
 
 pub use builder::{RegexBuilder, RegexSetBuilder};
-pub use re::Regex;
+pub use matcher::{Matcher, SomFallback, TextMatch};
+#[cfg(feature = "prefilter")]
+pub use prefilter::PrefilterMatcher;
+pub use re::{Match, Matches, Regex, Split, SplitN};
+pub use set::{RegexSet, SetMatches, SetMatchesIntoIter, SetMatchesIter};
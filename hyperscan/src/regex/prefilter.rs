@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use anyhow::{bail, Context, Result};
+
+use crate::common::Block;
+use crate::compile::{Builder, Flags, Patterns};
+use crate::errors::Error;
+use crate::runtime::{Matching, Scanner};
+
+/// A two-stage matcher for the patterns which Hyperscan can't support natively, like backreferences and lookaround.
+///
+/// The unsupported patterns, and those explicitly compiled with `PREFILTER`, are compiled in prefiltering mode,
+/// where Hyperscan reports a superset of their matches. Each candidate match is confirmed by `fancy_regex`,
+/// searching only up to the end offset of the candidate, before the callback is invoked.
+/// Since `fancy_regex` always matches UTF-8 text with Unicode classes,
+/// the prefiltered patterns are compiled with `UTF8` and `UCP`,
+/// and a prefiltered pattern with `SINGLEMATCH` reports its first confirmed match only.
+///
+/// The other patterns are compiled with `SOM_LEFTMOST` and reported directly while scanning,
+/// the confirmed matches are reported after the scan, ordered by the pattern ID.
+/// Note that Hyperscan reports every (possibly overlapping) match of a native pattern,
+/// while the confirmed matches of a prefiltered pattern are leftmost-first and non-overlapping.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::regex::PrefilterMatcher;
+/// let patterns: Patterns = "1:/(\\w+) \\1/\n2:/foo(?=bar)/\n3:/bar/".parse().unwrap();
+/// let matcher = PrefilterMatcher::new(patterns).unwrap();
+///
+/// assert!(matcher.is_prefiltered(1));
+/// assert!(matcher.is_prefiltered(2));
+/// assert!(!matcher.is_prefiltered(3));
+///
+/// let mut matches = vec![];
+///
+/// matcher
+///     .scan("foobar hello hello", |id, range| {
+///         matches.push((id, range));
+///         Matching::Continue
+///     })
+///     .unwrap();
+///
+/// assert_eq!(matches, vec![(3, 3..6), (1, 7..18), (2, 0..3)]);
+/// ```
+pub struct PrefilterMatcher {
+    scanner: Scanner<Block>,
+    confirm: HashMap<u32, Confirm>,
+}
+
+struct Confirm {
+    regex: fancy_regex::Regex,
+    single_match: bool,
+}
+
+impl PrefilterMatcher {
+    /// Compile the patterns, falling back to the prefiltering mode for the patterns Hyperscan can't support.
+    pub fn new(patterns: Patterns) -> Result<PrefilterMatcher> {
+        let mut confirm = HashMap::new();
+
+        let patterns = patterns
+            .iter()
+            .enumerate()
+            .map(|(i, pattern)| {
+                let id = pattern.id.unwrap_or(i) as u32;
+                let mut pattern = pattern.clone();
+
+                if pattern.flags.contains(Flags::PREFILTER) || pattern.info().is_err() {
+                    if !pattern.ext.is_empty() {
+                        bail!("pattern {} can't be confirmed with the extended parameters", id);
+                    }
+
+                    let re = confirm_regex(&pattern.expression, pattern.flags)
+                        .with_context(|| format!("confirm pattern {}", id))?;
+
+                    confirm.insert(
+                        id,
+                        Confirm {
+                            regex: re,
+                            single_match: pattern.flags.contains(Flags::SINGLEMATCH),
+                        },
+                    );
+
+                    pattern.flags.remove(Flags::SOM_LEFTMOST | Flags::SINGLEMATCH);
+                    pattern.flags |= Flags::PREFILTER | Flags::UTF8 | Flags::UCP;
+                } else {
+                    pattern.flags |= Flags::SOM_LEFTMOST;
+                }
+
+                Ok(pattern)
+            })
+            .collect::<Result<Patterns>>()?;
+
+        Ok(PrefilterMatcher {
+            scanner: Scanner::new(patterns.build()?)?,
+            confirm,
+        })
+    }
+
+    /// Returns `true` if the pattern is compiled in prefiltering mode, and its matches are confirmed.
+    pub fn is_prefiltered(&self, id: u32) -> bool {
+        self.confirm.contains_key(&id)
+    }
+
+    /// Scan the text, invoking the callback with the pattern ID and the byte range of each match.
+    pub fn scan<F>(&self, text: &str, mut on_match: F) -> Result<()>
+    where
+        F: FnMut(u32, Range<usize>) -> Matching,
+    {
+        let mut candidates = vec![];
+
+        self.scanner.scan(text, |id, from, to, _| {
+            if self.confirm.contains_key(&id) {
+                candidates.push((id, to as usize));
+
+                Matching::Continue
+            } else {
+                on_match(id, from as usize..to as usize)
+            }
+        })?;
+
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let mut windows = HashMap::new();
+
+        for (id, to) in candidates {
+            let confirm = &self.confirm[&id];
+            let window = windows.entry(id).or_insert_with(Window::default);
+
+            while let Some(m) = window
+                .next_match(&confirm.regex, text, to)
+                .with_context(|| format!("confirm pattern {}", id))?
+            {
+                if confirm.single_match {
+                    window.done = true;
+                }
+
+                if on_match(id, m) == Matching::Terminate {
+                    return Err(Error::ScanTerminated.into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The confirmed part of the text for a prefiltered pattern.
+///
+/// The matches are searched leftmost-first and non-overlapping like `fancy_regex::Regex::find_iter`,
+/// but a match is only reported once a candidate at or after its end offset arrives,
+/// and a match ending later is kept until then.
+#[derive(Default)]
+struct Window {
+    start: usize,
+    last_end: Option<usize>,
+    pending: Option<Range<usize>>,
+    done: bool,
+}
+
+impl Window {
+    /// Returns the next match ending at or before the `to` offset of the candidate.
+    fn next_match(&mut self, re: &fancy_regex::Regex, text: &str, to: usize) -> Result<Option<Range<usize>>> {
+        while self.pending.is_none() && !self.done {
+            if self.start > text.len() {
+                self.done = true;
+                break;
+            }
+
+            match re.find_from_pos(text, self.start)? {
+                Some(m) if m.start() == m.end() => {
+                    self.start = next_char(text, m.end());
+
+                    // Don't accept empty matches immediately following a match.
+                    if self.last_end != Some(m.end()) {
+                        self.pending = Some(m.range());
+                    }
+                }
+                Some(m) => {
+                    self.start = m.end();
+                    self.pending = Some(m.range());
+                }
+                None => self.done = true,
+            }
+        }
+
+        match self.pending.take() {
+            Some(m) if m.end <= to => {
+                self.last_end = Some(m.end);
+
+                Ok(Some(m))
+            }
+            pending => {
+                self.pending = pending;
+
+                Ok(None)
+            }
+        }
+    }
+}
+
+fn next_char(text: &str, off: usize) -> usize {
+    off + text[off..].chars().next().map_or(1, char::len_utf8)
+}
+
+fn confirm_regex(expr: &str, flags: Flags) -> Result<fancy_regex::Regex> {
+    let mut inline = String::new();
+
+    if flags.contains(Flags::CASELESS) {
+        inline.push('i');
+    }
+    if flags.contains(Flags::MULTILINE) {
+        inline.push('m');
+    }
+    if flags.contains(Flags::DOTALL) {
+        inline.push('s');
+    }
+
+    let expr = if inline.is_empty() {
+        expr.to_owned()
+    } else {
+        format!("(?{}){}", inline, expr)
+    };
+
+    Ok(fancy_regex::Regex::new(&expr)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_prefilter_matcher() {
+        let matcher = PrefilterMatcher::new("/(a+)b\\1/\n/b/\n/X(?!y)/i\n/c/P".parse().unwrap()).unwrap();
+
+        assert!(matcher.is_prefiltered(0));
+        assert!(!matcher.is_prefiltered(1));
+        assert!(matcher.is_prefiltered(2));
+        assert!(matcher.is_prefiltered(3));
+
+        let mut matches = vec![];
+
+        matcher
+            .scan("aabaab xy xz c", |id, range| {
+                matches.push((id, range));
+                Matching::Continue
+            })
+            .unwrap();
+
+        assert_eq!(matches, vec![(1, 2..3), (1, 5..6), (0, 0..5), (2, 10..11), (3, 13..14)]);
+
+        let mut matches = vec![];
+        let err = matcher
+            .scan("aba ab", |id, range| {
+                matches.push((id, range));
+
+                if id == 0 {
+                    Matching::Terminate
+                } else {
+                    Matching::Continue
+                }
+            })
+            .unwrap_err();
+
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ScanTerminated));
+        assert_eq!(matches, vec![(1, 1..2), (1, 5..6), (0, 0..3)]);
+
+        let patterns = vec![pattern! {"(a)\\1"; ; min_length = 2}].into_iter().collect();
+
+        assert!(PrefilterMatcher::new(patterns).is_err());
+    }
+
+    #[test]
+    fn test_prefilter_flags() {
+        let matcher = PrefilterMatcher::new("/(a)\\1/H\n/(\\w)\\1/".parse().unwrap()).unwrap();
+
+        assert!(matcher.is_prefiltered(0));
+        assert!(matcher.is_prefiltered(1));
+
+        let mut matches = vec![];
+
+        matcher
+            .scan("aa aa \u{e9}\u{e9}", |id, range| {
+                matches.push((id, range));
+                Matching::Continue
+            })
+            .unwrap();
+
+        assert_eq!(matches, vec![(0, 0..2), (1, 0..2), (1, 3..5), (1, 6..10)]);
+    }
+}
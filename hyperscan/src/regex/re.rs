@@ -5,9 +5,9 @@ use std::vec;
 
 use anyhow::{Error, Result};
 
-use crate::common::BlockDatabase;
+use crate::common::Block;
 use crate::compile::{Builder, Flags, Pattern};
-use crate::runtime::Matching;
+use crate::runtime::{Matching, Scanner};
 
 /// Match represents a single match of a regex in a haystack.
 ///
@@ -101,8 +101,10 @@ impl<'t> DoubleEndedIterator for Matches<'t> {
 }
 
 /// A compiled regular expression for matching Unicode strings.
+///
+/// The scratch space is managed by the regex, one per thread.
 #[derive(Clone)]
-pub struct Regex(pub(crate) Arc<BlockDatabase>);
+pub struct Regex(pub(crate) Arc<Scanner<Block>>);
 
 impl FromStr for Regex {
     type Err = Error;
@@ -126,7 +128,8 @@ impl Regex {
     pub(crate) fn with_flags<S: Into<String>>(re: S, flags: Flags) -> Result<Regex> {
         Pattern::with_flags(re, flags | Flags::SOM_LEFTMOST | Flags::UTF8)?
             .build()
-            .map(|db| Regex(Arc::new(db)))
+            .and_then(Scanner::new)
+            .map(|scanner| Regex(Arc::new(scanner)))
     }
 
     /// Returns true if and only if the regex matches the string given.
@@ -146,8 +149,7 @@ impl Regex {
     pub fn is_match(&self, text: &str) -> bool {
         let mut matched = false;
 
-        let s = self.0.alloc_scratch().unwrap();
-        let _ = self.0.scan(text, &s, |_, _, _, _| {
+        let _ = self.0.scan(text, |_, _, _, _| {
             matched = true;
 
             Matching::Terminate
@@ -172,18 +174,7 @@ impl Regex {
     /// assert_eq!(mat.end(), 15);
     /// ```
     pub fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
-        let mut matched = vec![];
-
-        let s = self.0.alloc_scratch().unwrap();
-        let _ = self.0.scan(text, &s, |_, from, to, _| {
-            matched.push((from as usize, to as usize));
-
-            Matching::Terminate
-        });
-
-        matched
-            .first()
-            .map(|&(start, end)| Match::new(&text[start..end], start, end))
+        self.find_iter(text).next()
     }
 
     /// Returns an iterator for each successive non-overlapping match in
@@ -205,8 +196,7 @@ impl Regex {
     pub fn find_iter<'t>(&self, text: &'t str) -> Matches<'t> {
         let mut matched = Vec::<Range<usize>>::new();
 
-        let s = self.0.alloc_scratch().unwrap();
-        let _ = self.0.scan(text, &s, |_, from, to, _| {
+        let _ = self.0.scan(text, |_, from, to, _| {
             let range = from as usize..to as usize;
 
             match matched.last() {
@@ -340,6 +330,17 @@ impl<'t> Iterator for SplitN<'t> {
 
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn test_find() {
+        let regex = super::Regex::new(r"\d+").unwrap();
+        let mat = regex.find("foo 123 bar 45").unwrap();
+
+        assert_eq!(mat.range(), 4..7);
+        assert_eq!(mat.as_str(), "123");
+        assert!(regex.is_match("45"));
+        assert!(regex.find("foo").is_none());
+    }
+
     #[test]
     fn test_find_iter() {
         let regex = r"\b\w{13}\b";
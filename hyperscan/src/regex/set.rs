@@ -0,0 +1,226 @@
+use std::iter::FromIterator;
+use std::sync::Arc;
+use std::vec;
+
+use anyhow::Result;
+
+use crate::common::Block;
+use crate::compile::{Builder, Flags, Pattern, Patterns};
+use crate::runtime::{Matching, Scanner};
+
+/// Match multiple (possibly overlapping) regular expressions in a single scan.
+///
+/// A regex set corresponds to the union of two or more regular expressions.
+/// That is, a regex set will match text where at least one of its constituent regular expressions matches.
+/// A regex set as its formulated here provides a touch more power:
+/// it will also report which regular expressions in the set match.
+///
+/// The scratch space is managed by the set, one per thread.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::regex::RegexSet;
+/// let set = RegexSet::new(&[r"\w+", r"\d+", r"\pL+", r"foo", r"bar", r"barfoo", r"foobar"]).unwrap();
+///
+/// let matches: Vec<_> = set.matches("foobar").into_iter().collect();
+/// assert_eq!(matches, vec![0, 2, 3, 4, 6]);
+///
+/// let matches = set.matches("foobar");
+/// assert!(!matches.matched(5));
+/// assert!(matches.matched(6));
+/// ```
+#[derive(Clone)]
+pub struct RegexSet(Arc<Scanner<Block>>, usize);
+
+impl RegexSet {
+    /// Create a new regex set with the given regular expressions.
+    ///
+    /// If any regular expressions are invalid, then an error is returned.
+    pub fn new<I, S>(exprs: I) -> Result<RegexSet>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::with_flags(exprs, Flags::empty())
+    }
+
+    pub(crate) fn with_flags<I, S>(exprs: I, flags: Flags) -> Result<RegexSet>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns = exprs
+            .into_iter()
+            .enumerate()
+            .map(|(id, expr)| {
+                Pattern::with_flags(expr.as_ref(), flags | Flags::SINGLEMATCH | Flags::UTF8).map(|p| p.with_id(id))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let len = patterns.len();
+        let db = Patterns::from_iter(patterns).build()?;
+
+        Ok(RegexSet(Arc::new(Scanner::new(db)?), len))
+    }
+
+    /// Returns true if and only if one of the regexes in this set matches the text given.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::regex::RegexSet;
+    /// let set = RegexSet::new(&[r"\d+", r"foo"]).unwrap();
+    ///
+    /// assert!(set.is_match("foo"));
+    /// assert!(!set.is_match("bar"));
+    /// ```
+    pub fn is_match(&self, text: &str) -> bool {
+        let mut matched = false;
+
+        let _ = self.0.scan(text, |_, _, _, _| {
+            matched = true;
+
+            Matching::Terminate
+        });
+
+        matched
+    }
+
+    /// Returns the set of regular expressions that match in the given text.
+    ///
+    /// The set returned contains the index of each regular expression that matches in the given text.
+    /// The index is in correspondence with the order of regular expressions given to `RegexSet`'s constructor.
+    pub fn matches(&self, text: &str) -> SetMatches {
+        let mut matches = vec![false; self.1];
+        let mut matched_any = false;
+
+        let _ = self.0.scan(text, |id, _, _, _| {
+            if let Some(matched) = matches.get_mut(id as usize) {
+                *matched = true;
+                matched_any = true;
+            }
+
+            Matching::Continue
+        });
+
+        SetMatches { matched_any, matches }
+    }
+
+    /// Returns the total number of regular expressions in this set.
+    pub fn len(&self) -> usize {
+        self.1
+    }
+
+    /// Returns `true` if this set contains no regular expressions.
+    pub fn is_empty(&self) -> bool {
+        self.1 == 0
+    }
+}
+
+/// A set of matches returned by a regex set.
+#[derive(Clone, Debug)]
+pub struct SetMatches {
+    matched_any: bool,
+    matches: Vec<bool>,
+}
+
+impl SetMatches {
+    /// Whether this set contains any matches.
+    pub fn matched_any(&self) -> bool {
+        self.matched_any
+    }
+
+    /// Whether the regex at the given index matched.
+    ///
+    /// # Panics
+    ///
+    /// If `regex_index` is greater than or equal to `self.len()`.
+    pub fn matched(&self, regex_index: usize) -> bool {
+        self.matches[regex_index]
+    }
+
+    /// The total number of regexes in the set that created these matches.
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Returns `true` if the set that created these matches contains no regexes.
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+
+    /// Returns an iterator over indexes in the regex that matched.
+    pub fn iter(&self) -> SetMatchesIter<'_> {
+        SetMatchesIter(self.matches.iter().enumerate())
+    }
+}
+
+impl IntoIterator for SetMatches {
+    type IntoIter = SetMatchesIntoIter;
+    type Item = usize;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SetMatchesIntoIter(self.matches.into_iter().enumerate())
+    }
+}
+
+impl<'a> IntoIterator for &'a SetMatches {
+    type IntoIter = SetMatchesIter<'a>;
+    type Item = usize;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An owned iterator over the set of matches from a regex set.
+pub struct SetMatchesIntoIter(std::iter::Enumerate<vec::IntoIter<bool>>);
+
+impl Iterator for SetMatchesIntoIter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        self.0.find_map(|(idx, matched)| if matched { Some(idx) } else { None })
+    }
+}
+
+/// A borrowed iterator over the set of matches from a regex set.
+///
+/// The lifetime `'a` refers to the lifetime of a `SetMatches` value.
+pub struct SetMatchesIter<'a>(std::iter::Enumerate<std::slice::Iter<'a, bool>>);
+
+impl<'a> Iterator for SetMatchesIter<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        self.0
+            .find_map(|(idx, &matched)| if matched { Some(idx) } else { None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regex_set() {
+        let exprs = [r"\w+", r"\d+", r"\pL+", r"foo", r"bar", r"barfoo", r"foobar"];
+        let text = "foobar";
+
+        let set = RegexSet::new(&exprs).unwrap();
+        let expected = regex::RegexSet::new(&exprs).unwrap();
+
+        assert_eq!(set.len(), expected.len());
+        assert_eq!(set.is_match(text), expected.is_match(text));
+        assert_eq!(
+            set.matches(text).into_iter().collect::<Vec<_>>(),
+            expected.matches(text).into_iter().collect::<Vec<_>>()
+        );
+
+        let matches = set.matches("123");
+
+        assert!(matches.matched_any());
+        assert_eq!(matches.iter().collect::<Vec<_>>(), vec![0, 1]);
+        assert!(!set.matches("!").matched_any());
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use anyhow::Result;
+use futures::stream::Stream as AsyncStream;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::common::{DatabaseRef, Streaming};
+use crate::runtime::{Match, Matching, Scratch, ScratchRef, Stream};
+
+const SCAN_BUF_SIZE: usize = 4096;
+
+impl DatabaseRef<Streaming> {
+    /// Scan the data from an async reader through a stream, which is closed at the end of the data.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// # #[tokio::main(basic_scheduler)]
+    /// # async fn main() {
+    /// let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let mut s = db.alloc_scratch().unwrap();
+    /// let mut matches = vec![];
+    ///
+    /// db.scan_async_read(&b"foo test bar"[..], &mut s, |_, from, to, _| {
+    ///     matches.push(from..to);
+    ///     Matching::Continue
+    /// })
+    /// .await
+    /// .unwrap();
+    ///
+    /// assert_eq!(matches, vec![4..8]);
+    /// # }
+    /// ```
+    pub async fn scan_async_read<R, F>(
+        &self,
+        mut reader: R,
+        scratch: &mut ScratchRef,
+        mut on_match_event: F,
+    ) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+        F: FnMut(u32, u64, u64, u32) -> Matching,
+    {
+        let stream = self.open_stream()?;
+        let mut buf = vec![0; SCAN_BUF_SIZE];
+
+        loop {
+            let res = match reader.read(&mut buf[..]).await {
+                Ok(0) => break,
+                Ok(len) => stream.scan(&buf[..len], scratch, &mut on_match_event),
+                Err(err) => Err(err.into()),
+            };
+
+            if let Err(err) = res {
+                let _ = stream.close(scratch, ());
+
+                return Err(err);
+            }
+        }
+
+        stream.close(scratch, &mut on_match_event)
+    }
+
+    /// Scan the chunks from an async stream through a Hyperscan stream, yielding the matches as an async stream.
+    ///
+    /// The Hyperscan stream is closed at the end of the chunks, the matches at the end of the data are yielded last.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use futures::{executor::block_on, stream::{self, StreamExt}};
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// let chunks = stream::iter(vec![bytes::Bytes::from("foo te"), bytes::Bytes::from("st bar")]);
+    /// let matches = db.match_stream(chunks, s).unwrap();
+    /// let matches = block_on(matches.map(|m| m.unwrap().range()).collect::<Vec<_>>());
+    ///
+    /// assert_eq!(matches, vec![4..8]);
+    /// ```
+    pub fn match_stream<S>(&self, chunks: S, scratch: Scratch) -> Result<MatchStream<S>>
+    where
+        S: AsyncStream + Unpin,
+        S::Item: AsRef<[u8]>,
+    {
+        Ok(MatchStream {
+            chunks,
+            stream: Some(self.open_stream()?),
+            scratch: Some(scratch),
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+/// An async stream of the matches found in the chunks of an async stream.
+pub struct MatchStream<S> {
+    chunks: S,
+    stream: Option<Stream>,
+    scratch: Option<Scratch>,
+    pending: VecDeque<Match>,
+}
+
+impl<S> MatchStream<S> {
+    /// Consumes the stream of matches, returning the scratch space.
+    pub fn into_scratch(mut self) -> Scratch {
+        self.close();
+
+        self.scratch.take().unwrap()
+    }
+
+    fn close(&mut self) {
+        if let (Some(stream), Some(scratch)) = (self.stream.take(), self.scratch.as_ref()) {
+            let _ = stream.close(scratch, ());
+        }
+    }
+}
+
+impl<S> AsyncStream for MatchStream<S>
+where
+    S: AsyncStream + Unpin,
+    S::Item: AsRef<[u8]>,
+{
+    type Item = Result<Match>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(m) = this.pending.pop_front() {
+                return Poll::Ready(Some(Ok(m)));
+            }
+
+            if this.stream.is_none() {
+                return Poll::Ready(None);
+            }
+
+            let chunk = match Pin::new(&mut this.chunks).poll_next(cx) {
+                Poll::Ready(chunk) => chunk,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            let scratch = this.scratch.as_ref().unwrap();
+            let pending = &mut this.pending;
+            let on_match_event = |id, from, to, _| {
+                pending.push_back(Match { id, from, to });
+                Matching::Continue
+            };
+
+            let res = match chunk {
+                Some(data) => this.stream.as_ref().unwrap().scan(data, scratch, on_match_event),
+                None => this.stream.take().unwrap().close(scratch, on_match_event),
+            };
+
+            if let Err(err) = res {
+                this.close();
+
+                return Poll::Ready(Some(Err(err)));
+            }
+        }
+    }
+}
+
+impl<S> Drop for MatchStream<S> {
+    fn drop(&mut self) {
+        self.close()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use bytes::Bytes;
+    use futures::{
+        executor::block_on,
+        stream::{self, StreamExt},
+    };
+
+    use crate::prelude::*;
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[tokio::test]
+    async fn test_scan_async_read() {
+        let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let mut s = db.alloc_scratch().unwrap();
+        let mut data = vec![b'x'; 4094];
+        data.extend_from_slice(b"test");
+        let mut matches = vec![];
+
+        let fut = db.scan_async_read(&data[..], &mut s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        });
+
+        assert_send(&fut);
+
+        fut.await.unwrap();
+
+        assert_eq!(matches, vec![4094..4098]);
+    }
+
+    #[test]
+    fn test_match_stream() {
+        let db: StreamingDatabase = "/foo/L\n/bar$/L".parse().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let chunks = stream::iter(vec![Bytes::from("foo b"), Bytes::from("a"), Bytes::from("r foo bar")]);
+        let matches = db.match_stream(chunks, s).unwrap();
+
+        assert_send(&matches);
+
+        let matches = block_on(matches.map(|m| m.unwrap()).collect::<Vec<_>>());
+
+        assert_eq!(
+            matches.iter().map(|m| (m.id, m.range())).collect::<Vec<_>>(),
+            vec![(0, 0..3), (0, 8..11), (1, 12..15)]
+        );
+    }
+
+    #[test]
+    fn test_match_stream_into_scratch() {
+        let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let chunks = stream::iter(vec![Bytes::from("test"), Bytes::from("foo test")]);
+        let mut matches = db.match_stream(chunks, s).unwrap();
+
+        assert_eq!(block_on(matches.next()).unwrap().unwrap().range(), 0..4);
+
+        let s = matches.into_scratch();
+        let st = db.open_stream().unwrap();
+        let mut matches = vec![];
+
+        st.scan("foo test", &s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        })
+        .unwrap();
+        st.close(&s, ()).unwrap();
+
+        assert_eq!(matches, vec![4..8]);
+    }
+}
@@ -0,0 +1,104 @@
+use std::io::IoSlice;
+
+use anyhow::Result;
+use arrayvec::ArrayVec;
+
+use crate::common::{Block, DatabaseRef, Vectored};
+use crate::runtime::{Match, Matching, ScratchRef};
+
+/// Up to `N` matches collected into a stack-allocated buffer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MatchesN<const N: usize> {
+    /// The first `N` matches.
+    pub matches: ArrayVec<Match, N>,
+    /// The number of matches which didn't fit in the buffer.
+    pub overflow: usize,
+}
+
+impl<const N: usize> MatchesN<N> {
+    /// Returns `true` if some matches didn't fit in the buffer.
+    pub fn is_overflow(&self) -> bool {
+        self.overflow > 0
+    }
+
+    fn push(&mut self, id: u32, from: u64, to: u64) -> Matching {
+        if self.matches.try_push(Match { id, from, to }).is_err() {
+            self.overflow += 1;
+        }
+
+        Matching::Continue
+    }
+}
+
+impl DatabaseRef<Block> {
+    /// Scan the data, collecting up to `N` matches into a stack-allocated buffer.
+    ///
+    /// The following matches are counted as overflow, the scan is not terminated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: BlockDatabase = pattern! {"a"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let res = db.scan_collect_n::<_, 2>("banana", &s).unwrap();
+    ///
+    /// assert_eq!(res.matches.iter().map(|m| m.range()).collect::<Vec<_>>(), vec![1..2, 3..4]);
+    /// assert!(res.is_overflow());
+    /// assert_eq!(res.overflow, 1);
+    /// ```
+    pub fn scan_collect_n<T, const N: usize>(&self, data: T, scratch: &ScratchRef) -> Result<MatchesN<N>>
+    where
+        T: AsRef<[u8]>,
+    {
+        let mut res = MatchesN::default();
+
+        self.scan(data, scratch, |id, from, to, _| res.push(id, from, to))
+            .map(|_| res)
+    }
+}
+
+impl DatabaseRef<Vectored> {
+    /// Scan the `IoSlice`s, collecting up to `N` matches into a stack-allocated buffer.
+    ///
+    /// No memory is allocated in release builds when there are at most 16 slices,
+    /// debug builds check the database with `DatabaseRef::validate_for_scan` first, which does allocate.
+    pub fn scan_collect_n<const N: usize>(&self, slices: &[IoSlice<'_>], scratch: &ScratchRef) -> Result<MatchesN<N>> {
+        let mut res = MatchesN::default();
+
+        self.scan_io_slices(slices, scratch, |id, from, to, _| res.push(id, from, to))
+            .map(|_| res)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::IoSlice;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_scan_collect_n() {
+        let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let res = db.scan_collect_n::<_, 4>("test foo test", &s).unwrap();
+
+        assert_eq!(res.matches.len(), 2);
+        assert!(!res.is_overflow());
+
+        let res = db.scan_collect_n::<_, 0>("test", &s).unwrap();
+
+        assert!(res.matches.is_empty());
+        assert_eq!(res.overflow, 1);
+
+        let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let slices = [IoSlice::new(b"te"), IoSlice::new(b"st te"), IoSlice::new(b"st")];
+        let res = db.scan_collect_n::<1>(&slices, &s).unwrap();
+
+        assert_eq!(res.matches[0].range(), 0..4);
+        assert_eq!(res.overflow, 1);
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::ffi;
+use crate::runtime::MatchEventHandler;
+
+/// Per-pattern match counters, which can be passed to the scan calls instead of a match callback.
+///
+/// The trampoline only increments the counter of the matched pattern,
+/// without calling any user callback or allocating memory.
+/// The matches of the pattern IDs out of range are counted as overflow.
+///
+/// The counters are atomic, so they can be shared by the scanning threads.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::Counters;
+/// let db: StreamingDatabase = "/foo/\n/bar/".parse().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let st = db.open_stream().unwrap();
+/// let counters = Counters::new(2);
+///
+/// for data in &["foo ba", "r foo", " bar foo"] {
+///     st.scan(data, &s, &counters).unwrap();
+/// }
+/// st.close(&s, &counters).unwrap();
+///
+/// assert_eq!(counters.get(0), 3);
+/// assert_eq!(counters.get(1), 2);
+/// assert_eq!(counters.snapshot(), vec![3, 2]);
+/// ```
+#[derive(Debug, Default)]
+pub struct Counters {
+    counts: Box<[AtomicU64]>,
+    overflow: AtomicU64,
+}
+
+impl Counters {
+    /// Constructs the counters for the pattern IDs `0..len`.
+    pub fn new(len: usize) -> Self {
+        Counters {
+            counts: (0..len).map(|_| AtomicU64::new(0)).collect(),
+            overflow: AtomicU64::new(0),
+        }
+    }
+
+    /// The number of counters.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns `true` if there is no counter.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// The number of matches of the pattern, or zero if the ID is out of range.
+    pub fn get(&self, id: u32) -> u64 {
+        self.counts
+            .get(id as usize)
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
+    /// The number of matches of the pattern IDs out of range.
+    pub fn overflow(&self) -> u64 {
+        self.overflow.load(Ordering::Relaxed)
+    }
+
+    /// The total number of matches.
+    pub fn total(&self) -> u64 {
+        self.counts
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .sum::<u64>()
+            + self.overflow()
+    }
+
+    /// Returns the number of matches of each pattern.
+    pub fn snapshot(&self) -> Vec<u64> {
+        self.counts.iter().map(|count| count.load(Ordering::Relaxed)).collect()
+    }
+
+    /// Reset all the counters to zero, returning the number of matches of each pattern.
+    pub fn reset(&self) -> Vec<u64> {
+        self.overflow.store(0, Ordering::Relaxed);
+        self.counts
+            .iter()
+            .map(|count| count.swap(0, Ordering::Relaxed))
+            .collect()
+    }
+
+    fn incr(&self, id: u32) {
+        self.counts
+            .get(id as usize)
+            .unwrap_or(&self.overflow)
+            .fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl MatchEventHandler for &Counters {
+    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut libc::c_void) {
+        unsafe extern "C" fn trampoline(id: u32, _: u64, _: u64, _: u32, ctx: *mut libc::c_void) -> libc::c_int {
+            (*(ctx as *const Counters)).incr(id);
+
+            0
+        }
+
+        (Some(trampoline), *self as *const Counters as *mut _)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        let db: BlockDatabase = "/foo/\n/bar/\n2:/baz/".parse().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let counters = Counters::new(2);
+
+        db.scan("foo bar baz foo", &s, &counters).unwrap();
+
+        assert_eq!(counters.snapshot(), vec![2, 1]);
+        assert_eq!(counters.overflow(), 1);
+        assert_eq!(counters.total(), 4);
+        assert_eq!(counters.reset(), vec![2, 1]);
+        assert_eq!(counters.total(), 0);
+    }
+
+    #[test]
+    fn test_shared_counters() {
+        let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+        let db = Arc::new(db);
+        let counters = Arc::new(Counters::new(1));
+
+        let handles = (0..4)
+            .map(|_| {
+                let db = db.clone();
+                let counters = counters.clone();
+
+                thread::spawn(move || {
+                    let s = db.alloc_scratch().unwrap();
+
+                    for _ in 0..100 {
+                        db.scan("test test", &s, &*counters).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(counters.get(0), 800);
+    }
+}
@@ -0,0 +1,239 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+
+use crate::common::{Block, Database};
+use crate::runtime::{Matching, Scanner};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// When a rule group is scanned.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schedule {
+    /// Scan every record.
+    Always,
+    /// Scan one of every `N` records, starting with the first one.
+    Sample(u64),
+    /// Scan the records until the scanned bytes reach the percentage of all the bytes.
+    Bytes(f64),
+    /// Scan the records during a time window of the day in UTC, the window may wrap around midnight.
+    Window(Range<Duration>),
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule::Always
+    }
+}
+
+struct RuleGroup {
+    name: String,
+    scanner: Scanner<Block>,
+    schedule: Schedule,
+    records: AtomicU64,
+    bytes: AtomicU64,
+    scanned_bytes: AtomicU64,
+    runs: AtomicU64,
+}
+
+impl RuleGroup {
+    fn should_run(&self, len: u64, time_of_day: Duration) -> bool {
+        let records = self.records.fetch_add(1, Ordering::Relaxed);
+        let bytes = self.bytes.fetch_add(len, Ordering::Relaxed) + len;
+
+        let run = match self.schedule {
+            Schedule::Always => true,
+            Schedule::Sample(n) => records % n.max(1) == 0,
+            Schedule::Bytes(percent) => {
+                (self.scanned_bytes.load(Ordering::Relaxed) as f64) < bytes as f64 * percent / 100.0
+            }
+            Schedule::Window(Range { start, end }) if start <= end => start <= time_of_day && time_of_day < end,
+            Schedule::Window(Range { start, end }) => start <= time_of_day || time_of_day < end,
+        };
+
+        if run {
+            self.scanned_bytes.fetch_add(len, Ordering::Relaxed);
+            self.runs.fetch_add(1, Ordering::Relaxed);
+        }
+
+        run
+    }
+}
+
+/// The block mode rule groups, each of them is scanned according to its schedule.
+///
+/// The expensive rule groups (like the forensic rules) can be scanned on a sample of the records
+/// or during the off-peak hours, so they don't consume the full line-rate budget.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::{RuleGroups, Schedule};
+/// let mut groups = RuleGroups::new();
+///
+/// groups
+///     .add("fast", pattern! {"test"; SOM_LEFTMOST}.build().unwrap(), Schedule::Always)
+///     .unwrap()
+///     .add("forensic", pattern! {"t.*t"; SOM_LEFTMOST}.build().unwrap(), Schedule::Sample(2))
+///     .unwrap();
+///
+/// let mut matches = vec![];
+///
+/// for _ in 0..2 {
+///     groups
+///         .scan("foo test bar", |group: &str, _, from, to| {
+///             matches.push((group.to_owned(), from..to));
+///             Matching::Continue
+///         })
+///         .unwrap();
+/// }
+///
+/// assert_eq!(
+///     matches,
+///     vec![("fast".to_owned(), 4..8), ("forensic".to_owned(), 4..8), ("fast".to_owned(), 4..8)]
+/// );
+/// assert_eq!(groups.runs("forensic"), Some(1));
+/// ```
+#[derive(Default)]
+pub struct RuleGroups {
+    groups: Vec<RuleGroup>,
+}
+
+impl RuleGroups {
+    /// Constructs an empty rule groups.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a named rule group scanned according to the schedule.
+    pub fn add<S: Into<String>>(&mut self, name: S, db: Database<Block>, schedule: Schedule) -> Result<&mut Self> {
+        self.groups.push(RuleGroup {
+            name: name.into(),
+            scanner: Scanner::new(db)?,
+            schedule,
+            records: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            scanned_bytes: AtomicU64::new(0),
+            runs: AtomicU64::new(0),
+        });
+
+        Ok(self)
+    }
+
+    /// The number of rule groups.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Returns `true` if there is no rule group.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The number of records scanned by the rule group.
+    pub fn runs(&self, name: &str) -> Option<u64> {
+        self.groups
+            .iter()
+            .find(|group| group.name == name)
+            .map(|group| group.runs.load(Ordering::Relaxed))
+    }
+
+    /// Scan the record with the scheduled rule groups in order, returning the number of scanned groups.
+    ///
+    /// The match callback is invoked with the name of the rule group, and the ID and offsets of the match.
+    pub fn scan<D, F>(&self, data: D, on_match_event: F) -> Result<usize>
+    where
+        D: AsRef<[u8]>,
+        F: FnMut(&str, u32, u64, u64) -> Matching,
+    {
+        let time_of_day = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(Duration::default(), |d| {
+                Duration::from_nanos((d.as_nanos() % DAY.as_nanos()) as u64)
+            });
+
+        self.scan_at(data.as_ref(), time_of_day, on_match_event)
+    }
+
+    fn scan_at<F>(&self, data: &[u8], time_of_day: Duration, mut on_match_event: F) -> Result<usize>
+    where
+        F: FnMut(&str, u32, u64, u64) -> Matching,
+    {
+        let mut scanned = 0;
+
+        for group in &self.groups {
+            if group.should_run(data.len() as u64, time_of_day) {
+                let name = group.name.as_str();
+
+                group
+                    .scanner
+                    .scan(data, |id, from, to, _| on_match_event(name, id, from, to))?;
+
+                scanned += 1;
+            }
+        }
+
+        Ok(scanned)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+
+    use super::*;
+
+    fn single_group(schedule: Schedule) -> RuleGroups {
+        let mut groups = RuleGroups::new();
+
+        groups
+            .add("test", pattern! {"test"}.build().unwrap(), schedule)
+            .unwrap();
+        groups
+    }
+
+    fn nop(_: &str, _: u32, _: u64, _: u64) -> Matching {
+        Matching::Continue
+    }
+
+    #[test]
+    fn test_schedule_sample() {
+        let groups = single_group(Schedule::Sample(3));
+        let scanned = (0..7).map(|_| groups.scan("test", nop).unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(scanned, vec![1, 0, 0, 1, 0, 0, 1]);
+        assert_eq!(groups.runs("test"), Some(3));
+        assert_eq!(groups.runs("other"), None);
+    }
+
+    #[test]
+    fn test_schedule_bytes() {
+        let groups = single_group(Schedule::Bytes(25.0));
+
+        for _ in 0..100 {
+            groups.scan("test", nop).unwrap();
+        }
+
+        assert_eq!(groups.runs("test"), Some(25));
+    }
+
+    #[test]
+    fn test_schedule_window() {
+        let hour = |h: u64| Duration::from_secs(h * 60 * 60);
+
+        let groups = single_group(Schedule::Window(hour(1)..hour(5)));
+
+        assert_eq!(groups.scan_at(b"test", hour(0), nop).unwrap(), 0);
+        assert_eq!(groups.scan_at(b"test", hour(1), nop).unwrap(), 1);
+        assert_eq!(groups.scan_at(b"test", hour(5), nop).unwrap(), 0);
+
+        let groups = single_group(Schedule::Window(hour(22)..hour(2)));
+
+        assert_eq!(groups.scan_at(b"test", hour(23), nop).unwrap(), 1);
+        assert_eq!(groups.scan_at(b"test", hour(1), nop).unwrap(), 1);
+        assert_eq!(groups.scan_at(b"test", hour(12), nop).unwrap(), 0);
+    }
+}
@@ -0,0 +1,234 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The number of bits of the linear sub-buckets in a power of two range, about 3% precision.
+const SUB_BUCKET_BITS: u32 = 5;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const BUCKETS: usize = (64 - SUB_BUCKET_BITS as usize + 1) * SUB_BUCKETS;
+
+/// A lock-free HDR-style histogram of the scan latencies in nanoseconds.
+///
+/// The values are recorded into log-linear buckets, every power of two range is split into 32 linear sub-buckets,
+/// so the relative error of the percentiles is bounded by about 3% for any latency.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use hyperscan::LatencyHistogram;
+/// let histogram = LatencyHistogram::new();
+///
+/// for us in 1..=100 {
+///     histogram.record(Duration::from_micros(us));
+/// }
+///
+/// let snapshot = histogram.snapshot();
+///
+/// assert_eq!(snapshot.count(), 100);
+/// assert_eq!(snapshot.max(), Duration::from_micros(100));
+/// assert!(snapshot.percentile(50.0) >= Duration::from_micros(50));
+/// assert!(snapshot.percentile(50.0) < Duration::from_micros(52));
+/// ```
+pub struct LatencyHistogram {
+    counts: Box<[AtomicU64]>,
+    count: AtomicU64,
+    sum: AtomicU64,
+    max: AtomicU64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for LatencyHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LatencyHistogram")
+            .field("count", &self.count.load(Ordering::Relaxed))
+            .field("max", &self.max.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl LatencyHistogram {
+    /// Constructs an empty histogram.
+    pub fn new() -> Self {
+        LatencyHistogram {
+            counts: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+        }
+    }
+
+    /// Record the duration of a scan call.
+    pub fn record(&self, duration: Duration) {
+        let nanos = duration.as_nanos().min(u64::MAX as u128) as u64;
+
+        self.counts[bucket_index(nanos)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(nanos, Ordering::Relaxed);
+        self.max.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    /// Take a snapshot of the recorded latencies.
+    ///
+    /// The concurrent recordings may be partially included in the snapshot.
+    pub fn snapshot(&self) -> LatencySnapshot {
+        let counts = self
+            .counts
+            .iter()
+            .map(|c| c.load(Ordering::Relaxed))
+            .collect::<Vec<_>>();
+
+        LatencySnapshot {
+            count: counts.iter().sum(),
+            sum: self.sum.load(Ordering::Relaxed),
+            max: self.max.load(Ordering::Relaxed),
+            counts,
+        }
+    }
+
+    /// Clear the recorded latencies.
+    pub fn reset(&self) {
+        for c in self.counts.iter() {
+            c.store(0, Ordering::Relaxed);
+        }
+
+        self.count.store(0, Ordering::Relaxed);
+        self.sum.store(0, Ordering::Relaxed);
+        self.max.store(0, Ordering::Relaxed);
+    }
+}
+
+/// A point-in-time copy of a `LatencyHistogram`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LatencySnapshot {
+    counts: Vec<u64>,
+    count: u64,
+    sum: u64,
+    max: u64,
+}
+
+impl LatencySnapshot {
+    /// The number of recorded scan calls.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The mean latency, or zero if nothing was recorded.
+    pub fn mean(&self) -> Duration {
+        Duration::from_nanos(self.sum.checked_div(self.count).unwrap_or_default())
+    }
+
+    /// The maximum recorded latency.
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max)
+    }
+
+    /// The latency at the percentile (between 0 and 100), or zero if nothing was recorded.
+    ///
+    /// The highest value equivalent to the bucket of the percentile is returned, capped by the maximum.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        if self.count == 0 {
+            return Duration::from_nanos(0);
+        }
+
+        let rank = ((percentile.max(0.0).min(100.0) / 100.0 * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+
+        for (idx, &n) in self.counts.iter().enumerate() {
+            seen += n;
+
+            if seen >= rank {
+                return Duration::from_nanos(bucket_highest(idx).min(self.max));
+            }
+        }
+
+        self.max()
+    }
+
+    /// The median latency.
+    pub fn p50(&self) -> Duration {
+        self.percentile(50.0)
+    }
+
+    /// The 99th percentile latency.
+    pub fn p99(&self) -> Duration {
+        self.percentile(99.0)
+    }
+
+    /// The 99.9th percentile latency.
+    pub fn p999(&self) -> Duration {
+        self.percentile(99.9)
+    }
+}
+
+fn bucket_index(value: u64) -> usize {
+    if value < (SUB_BUCKETS as u64) << 1 {
+        value as usize
+    } else {
+        let shift = 64 - value.leading_zeros() - SUB_BUCKET_BITS - 1;
+        let mantissa = (value >> shift) as usize;
+
+        (shift as usize + 1) * SUB_BUCKETS + mantissa - SUB_BUCKETS
+    }
+}
+
+fn bucket_highest(idx: usize) -> u64 {
+    if idx < SUB_BUCKETS << 1 {
+        idx as u64
+    } else {
+        let shift = (idx / SUB_BUCKETS - 1) as u32;
+        let mantissa = (SUB_BUCKETS + idx % SUB_BUCKETS) as u64;
+
+        (mantissa << shift).saturating_add((1 << shift) - 1)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_index() {
+        for &v in &[0, 1, 63, 64, 65, 1000, 123_456_789, u64::MAX / 3, u64::MAX] {
+            let idx = bucket_index(v);
+
+            assert!(idx < BUCKETS);
+            assert!(bucket_highest(idx) >= v);
+            assert!(idx == 0 || bucket_highest(idx - 1) < v);
+            assert!(bucket_highest(idx) - v <= v / SUB_BUCKETS as u64);
+        }
+    }
+
+    #[test]
+    fn test_latency_histogram() {
+        let histogram = LatencyHistogram::new();
+
+        assert_eq!(histogram.snapshot().count(), 0);
+        assert_eq!(histogram.snapshot().p99(), Duration::from_nanos(0));
+
+        for ns in 1..=1000 {
+            histogram.record(Duration::from_nanos(ns));
+        }
+
+        let snapshot = histogram.snapshot();
+
+        assert_eq!(snapshot.count(), 1000);
+        assert_eq!(snapshot.mean(), Duration::from_nanos(500));
+        assert_eq!(snapshot.max(), Duration::from_nanos(1000));
+        assert_eq!(snapshot.percentile(0.0), Duration::from_nanos(1));
+        assert_eq!(snapshot.p50(), Duration::from_nanos(503));
+        assert_eq!(snapshot.p99(), Duration::from_nanos(991));
+        assert_eq!(snapshot.p999(), Duration::from_nanos(1000));
+        assert_eq!(snapshot.percentile(100.0), Duration::from_nanos(1000));
+
+        histogram.reset();
+
+        assert_eq!(histogram.snapshot().count(), 0);
+        assert_eq!(histogram.snapshot().max(), Duration::from_nanos(0));
+    }
+}
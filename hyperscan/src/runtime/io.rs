@@ -0,0 +1,197 @@
+use std::io::{self, ErrorKind, Read, Write};
+
+use anyhow::Result;
+
+use crate::common::{DatabaseRef, Streaming};
+use crate::runtime::{MatchEventHandler, ScratchRef, Stream};
+
+const SCAN_BUF_SIZE: usize = 4096;
+
+/// A writer which scans all the data written to it through a stream.
+///
+/// The stream is closed by `StreamWriter::close`, which reports the matches at the end of the data.
+/// If the writer is dropped without being closed, the stream is closed without reporting those matches.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::io::{self, Write};
+/// # use hyperscan::prelude::*;
+/// let db: StreamingDatabase = pattern! {"test$"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut matches = vec![];
+///
+/// let mut w = db
+///     .stream_writer(&s, |_, from, to, _| {
+///         matches.push(from..to);
+///         Matching::Continue
+///     })
+///     .unwrap();
+///
+/// write!(w, "foo te").unwrap();
+/// io::copy(&mut "st".as_bytes(), &mut w).unwrap();
+/// w.close().unwrap();
+///
+/// assert_eq!(matches, vec![4..8]);
+/// ```
+pub struct StreamWriter<'a, F: MatchEventHandler> {
+    stream: Option<Stream>,
+    scratch: &'a ScratchRef,
+    on_match_event: F,
+}
+
+impl<F: MatchEventHandler> StreamWriter<'_, F> {
+    /// Close the stream, reporting the matches at the end of the data.
+    pub fn close(mut self) -> Result<()> {
+        let stream = self.stream.take().expect("stream");
+        let handler = unsafe { self.on_match_event.split() };
+
+        stream.close(self.scratch, handler)
+    }
+}
+
+impl<F: MatchEventHandler> Write for StreamWriter<'_, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let stream = self.stream.as_ref().expect("stream");
+        let handler = unsafe { self.on_match_event.split() };
+
+        stream
+            .scan(buf, self.scratch, handler)
+            .map(|_| buf.len())
+            .map_err(|err| io::Error::new(ErrorKind::Other, err))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<F: MatchEventHandler> Drop for StreamWriter<'_, F> {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.close(self.scratch, ());
+        }
+    }
+}
+
+impl DatabaseRef<Streaming> {
+    /// Open a stream which scans the data written to the returned writer.
+    pub fn stream_writer<'a, F>(&self, scratch: &'a ScratchRef, on_match_event: F) -> Result<StreamWriter<'a, F>>
+    where
+        F: MatchEventHandler,
+    {
+        Ok(StreamWriter {
+            stream: Some(self.open_stream()?),
+            scratch,
+            on_match_event,
+        })
+    }
+
+    /// Scan the data from a reader through a stream, which is closed at the end of the data.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::fs::File;
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"hyperscan"; CASELESS}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut count = 0;
+    ///
+    /// db.scan_reader(File::open("Cargo.toml").unwrap(), &s, |_, _, _, _| {
+    ///     count += 1;
+    ///     Matching::Continue
+    /// })
+    /// .unwrap();
+    ///
+    /// assert!(count > 0);
+    /// ```
+    pub fn scan_reader<R, F>(&self, mut reader: R, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    where
+        R: Read,
+        F: MatchEventHandler,
+    {
+        let stream = self.open_stream()?;
+        let mut buf = [0; SCAN_BUF_SIZE];
+
+        let handler = unsafe { on_match_event.split() };
+
+        loop {
+            match reader.read(&mut buf[..]) {
+                Ok(0) => break,
+                Ok(len) => {
+                    if let Err(err) = stream.scan(&buf[..len], scratch, handler) {
+                        let _ = stream.close(scratch, ());
+
+                        return Err(err);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    let _ = stream.close(scratch, ());
+
+                    return Err(err.into());
+                }
+            }
+        }
+
+        stream.close(scratch, handler)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::{self, Cursor, Write};
+
+    use crate::prelude::*;
+    use crate::Error;
+
+    #[test]
+    fn test_stream_writer() {
+        let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        {
+            let mut w = db
+                .stream_writer(&s, |_, from, to, _| {
+                    matches.push(from..to);
+                    Matching::Continue
+                })
+                .unwrap();
+
+            w.write_all(b"foo t").unwrap();
+            w.write_all(b"es").unwrap();
+            w.write_all(b"t bar test").unwrap();
+            w.close().unwrap();
+        }
+
+        assert_eq!(matches, vec![4..8, 13..17]);
+
+        let mut w = db.stream_writer(&s, Matching::Terminate).unwrap();
+        let err = w.write_all(b"test").unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    }
+
+    #[test]
+    fn test_scan_reader() {
+        let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut data = vec![b'x'; 4094];
+        data.extend_from_slice(b"test");
+        let mut matches = vec![];
+
+        db.scan_reader(Cursor::new(&data), &s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![4094..4098]);
+
+        let err = db.scan_reader(Cursor::new(&data), &s, Matching::Terminate).unwrap_err();
+
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ScanTerminated));
+    }
+}
@@ -1,11 +1,47 @@
-mod closure;
+#[cfg(feature = "async")]
+mod async_stream;
+#[cfg(feature = "arrayvec")]
+mod collect;
+mod counters;
+mod groups;
+#[cfg(feature = "histogram")]
+mod histogram;
+mod io;
 #[cfg(feature = "pattern")]
 mod pattern;
+#[cfg(feature = "record")]
+mod record;
+#[cfg(feature = "record")]
+mod replay;
 mod scan;
+mod scanner;
 mod scratch;
+#[cfg(feature = "mmap")]
+mod segments;
+mod simple;
 mod stream;
+mod stream_map;
+mod validate;
+mod vectored;
 
-pub use self::closure::split_closure;
-pub use self::scan::{MatchEventHandler, Matching};
+#[cfg(feature = "async")]
+pub use self::async_stream::MatchStream;
+#[cfg(feature = "arrayvec")]
+pub use self::collect::MatchesN;
+pub use self::counters::Counters;
+pub use self::groups::{RuleGroups, Schedule};
+#[cfg(feature = "histogram")]
+pub use self::histogram::{LatencyHistogram, LatencySnapshot};
+pub use self::io::StreamWriter;
+#[cfg(feature = "record")]
+pub use self::record::{CaptureReader, Record, RecordKind, RecordedStream, Recorder};
+#[cfg(feature = "record")]
+pub use self::replay::{ReplayDiff, ReplayReport, Replayer};
+pub use self::scan::{Match, MatchEventHandler, Matching};
+pub use self::scanner::Scanner;
 pub use self::scratch::{Scratch, ScratchRef};
+#[cfg(feature = "mmap")]
+pub use self::segments::{Segment, SegmentMatch, Segments};
 pub use self::stream::{Stream, StreamRef};
+pub use self::stream_map::{ShutdownReport, StreamMap};
+pub use self::vectored::{VectoredBlock, VectoredBuffer, VectoredScannable};
@@ -0,0 +1,552 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::common::{Block, DatabaseRef, Streaming};
+use crate::ffi;
+use crate::runtime::{Match, MatchEventHandler, Matching, ScratchRef, Stream};
+use crate::Error;
+
+const MAGIC: &[u8; 8] = b"HSCAP002";
+
+const FLAG_TERMINATED: u8 = 1;
+
+/// The kind of a captured scan call.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordKind {
+    /// A block scan.
+    Block = 0,
+    /// The data written to a stream.
+    Stream = 1,
+    /// The close of a stream.
+    Close = 2,
+}
+
+/// A captured scan call with the matches it reported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// The kind of the scan call.
+    pub kind: RecordKind,
+    /// The ID of the recorded stream, or zero for a block scan.
+    pub stream: u64,
+    /// The scanned data, truncated to the record size cap of the recorder.
+    pub data: Vec<u8>,
+    /// The length of the scanned data before truncation.
+    pub len: u64,
+    /// The matches reported by the scan call.
+    pub matches: Vec<Match>,
+    /// The scan call was terminated by the callback, so the matches after the last recorded one are missing.
+    pub terminated: bool,
+}
+
+impl Record {
+    /// Returns `true` if the scanned data was truncated.
+    pub fn is_truncated(&self) -> bool {
+        (self.data.len() as u64) < self.len
+    }
+
+    /// Write the record in the capture format.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let flags = if self.terminated { FLAG_TERMINATED } else { 0 };
+
+        w.write_all(&[self.kind as u8, flags])?;
+        w.write_all(&self.stream.to_le_bytes())?;
+        w.write_all(&self.len.to_le_bytes())?;
+        w.write_all(&(self.data.len() as u64).to_le_bytes())?;
+        w.write_all(&self.data)?;
+        w.write_all(&(self.matches.len() as u32).to_le_bytes())?;
+
+        for m in &self.matches {
+            w.write_all(&m.id.to_le_bytes())?;
+            w.write_all(&m.from.to_le_bytes())?;
+            w.write_all(&m.to.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a record in the capture format, returns `None` at the end of the capture.
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Option<Record>> {
+        let mut kind = [0; 1];
+        let mut flags = [0; 1];
+
+        match r.read_exact(&mut kind) {
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            res => res.context("read record")?,
+        }
+
+        let kind = match kind[0] {
+            0 => RecordKind::Block,
+            1 => RecordKind::Stream,
+            2 => RecordKind::Close,
+            n => bail!("unknown record kind: {}", n),
+        };
+        r.read_exact(&mut flags).context("read record")?;
+        let stream = read_u64(r)?;
+        let len = read_u64(r)?;
+        let data_len = read_u64(r)?;
+        let mut data = vec![];
+        r.by_ref()
+            .take(data_len)
+            .read_to_end(&mut data)
+            .context("read record data")?;
+
+        if (data.len() as u64) < data_len {
+            bail!("truncated record data, expected {} bytes, got {}", data_len, data.len());
+        }
+
+        let matches = (0..read_u32(r)?)
+            .map(|_| {
+                Ok(Match {
+                    id: read_u32(r)?,
+                    from: read_u64(r)?,
+                    to: read_u64(r)?,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()
+            .context("read record matches")?;
+
+        Ok(Some(Record {
+            kind,
+            stream,
+            data,
+            len,
+            matches,
+            terminated: flags[0] & FLAG_TERMINATED != 0,
+        }))
+    }
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf).map(|_| u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf).map(|_| u64::from_le_bytes(buf))
+}
+
+/// A reader of the records in a capture written by a `Recorder`.
+#[derive(Debug)]
+pub struct CaptureReader<R> {
+    reader: R,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Constructs a reader of the capture, checking its header.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0; 8];
+
+        reader.read_exact(&mut magic).context("read capture header")?;
+
+        if &magic != MAGIC {
+            bail!("invalid capture header: {:?}", magic);
+        }
+
+        Ok(CaptureReader { reader })
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Record::read_from(&mut self.reader).transpose()
+    }
+}
+
+/// A recorder which tees the scanned data and the resulting matches into a capture,
+/// so the scans can be replayed offline.
+///
+/// Only one of every `n` block scans or streams is recorded with `Recorder::with_sampling`,
+/// the data of a record is truncated to `Recorder::with_max_record_len`,
+/// and the recording stops when the capture would exceed `Recorder::with_max_bytes`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::{CaptureReader, Recorder};
+/// let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let recorder = Recorder::new(vec![]).unwrap().with_max_record_len(1024);
+///
+/// recorder.scan(&db, "foo test bar", &s, Matching::Continue).unwrap();
+///
+/// let capture = recorder.into_inner().unwrap();
+/// let records = CaptureReader::new(&capture[..]).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+///
+/// assert_eq!(records.len(), 1);
+/// assert_eq!(records[0].data, b"foo test bar");
+/// assert_eq!(records[0].matches[0].range(), 4..8);
+/// ```
+#[derive(Debug)]
+pub struct Recorder<W> {
+    writer: Mutex<W>,
+    sample_every: u64,
+    max_record_len: usize,
+    max_bytes: u64,
+    calls: AtomicU64,
+    streams: AtomicU64,
+    written: AtomicU64,
+    full: AtomicBool,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Constructs a recorder which writes the capture to the writer.
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(MAGIC).context("write capture header")?;
+
+        Ok(Recorder {
+            writer: Mutex::new(writer),
+            sample_every: 1,
+            max_record_len: usize::max_value(),
+            max_bytes: u64::max_value(),
+            calls: AtomicU64::new(0),
+            streams: AtomicU64::new(0),
+            written: AtomicU64::new(MAGIC.len() as u64),
+            full: AtomicBool::new(false),
+        })
+    }
+
+    /// Record only one of every `n` block scans or streams.
+    pub fn with_sampling(mut self, n: u64) -> Self {
+        self.sample_every = n.max(1);
+        self
+    }
+
+    /// Truncate the data of a record to `len` bytes, the matches are always recorded.
+    pub fn with_max_record_len(mut self, len: usize) -> Self {
+        self.max_record_len = len;
+        self
+    }
+
+    /// Stop recording when the capture would exceed `n` bytes.
+    pub fn with_max_bytes(mut self, n: u64) -> Self {
+        self.max_bytes = n;
+        self
+    }
+
+    /// The number of bytes written to the capture.
+    pub fn written(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if the recording stopped because the capture reached its size cap.
+    pub fn is_full(&self) -> bool {
+        self.full.load(Ordering::Relaxed)
+    }
+
+    /// Flush the capture.
+    pub fn flush(&self) -> Result<()> {
+        self.writer()?.flush().context("flush capture")
+    }
+
+    /// Consumes the recorder, returning the writer of the capture.
+    pub fn into_inner(self) -> Result<W> {
+        let mut writer = self.writer.into_inner().map_err(|_| anyhow!("recorder poisoned"))?;
+
+        writer.flush().context("flush capture")?;
+
+        Ok(writer)
+    }
+
+    /// The block (non-streaming) regular expression scanner, which records the scan if it's sampled.
+    pub fn scan<D, F>(
+        &self,
+        db: &DatabaseRef<Block>,
+        data: D,
+        scratch: &ScratchRef,
+        mut on_match_event: F,
+    ) -> Result<()>
+    where
+        D: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        if !self.sample() {
+            return db.scan(data, scratch, on_match_event);
+        }
+
+        let data = data.as_ref();
+        let mut matches = vec![];
+        let res = db.scan(data, scratch, tee(&mut matches, unsafe { on_match_event.split() }));
+        let recorded = self.record(RecordKind::Block, 0, data, matches, is_terminated(&res));
+
+        res.and(recorded)
+    }
+
+    /// Open and initialise a stream, which records the data written to it if it's sampled.
+    pub fn open_stream(&self, db: &DatabaseRef<Streaming>) -> Result<RecordedStream<'_, W>> {
+        let id = if self.sample() {
+            Some(self.streams.fetch_add(1, Ordering::Relaxed) + 1)
+        } else {
+            None
+        };
+
+        Ok(RecordedStream {
+            stream: db.open_stream()?,
+            recorder: self,
+            id,
+        })
+    }
+
+    fn sample(&self) -> bool {
+        !self.is_full() && self.calls.fetch_add(1, Ordering::Relaxed) % self.sample_every == 0
+    }
+
+    fn writer(&self) -> Result<MutexGuard<'_, W>> {
+        self.writer.lock().map_err(|_| anyhow!("recorder poisoned"))
+    }
+
+    fn record(&self, kind: RecordKind, stream: u64, data: &[u8], matches: Vec<Match>, terminated: bool) -> Result<()> {
+        if self.is_full() {
+            return Ok(());
+        }
+
+        let record = Record {
+            kind,
+            stream,
+            data: data[..data.len().min(self.max_record_len)].to_vec(),
+            len: data.len() as u64,
+            matches,
+            terminated,
+        };
+        let mut buf = vec![];
+
+        record.write_to(&mut buf)?;
+
+        let mut writer = self.writer()?;
+        let written = self.written();
+
+        if written + buf.len() as u64 > self.max_bytes {
+            self.full.store(true, Ordering::Relaxed);
+
+            return Ok(());
+        }
+
+        writer.write_all(&buf).context("write capture")?;
+
+        self.written.store(written + buf.len() as u64, Ordering::Relaxed);
+
+        Ok(())
+    }
+}
+
+/// Returns `true` if the scan call was terminated by the callback.
+fn is_terminated(res: &Result<()>) -> bool {
+    res.as_ref().err().and_then(|err| err.downcast_ref::<Error>()) == Some(&Error::ScanTerminated)
+}
+
+/// Collect the matches, and forward them to the split match event handler.
+fn tee(
+    matches: &mut Vec<Match>,
+    (callback, userdata): (ffi::match_event_handler, *mut libc::c_void),
+) -> impl FnMut(u32, u64, u64, u32) -> Matching + '_ {
+    move |id, from, to, flags| {
+        matches.push(Match { id, from, to });
+
+        match callback {
+            Some(callback) if unsafe { callback(id, from, to, flags, userdata) } != 0 => Matching::Terminate,
+            _ => Matching::Continue,
+        }
+    }
+}
+
+/// A stream opened by a `Recorder`, which records the data written to it if it's sampled.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::{CaptureReader, RecordKind, Recorder};
+/// let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let recorder = Recorder::new(vec![]).unwrap();
+/// let st = recorder.open_stream(&db).unwrap();
+///
+/// for data in &["foo t", "es", "t bar"] {
+///     st.scan(data, &s, Matching::Continue).unwrap();
+/// }
+/// st.close(&s, Matching::Continue).unwrap();
+///
+/// let capture = recorder.into_inner().unwrap();
+/// let records = CaptureReader::new(&capture[..]).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+///
+/// assert_eq!(records.len(), 4);
+/// assert_eq!(records[2].matches[0].range(), 4..8);
+/// assert_eq!(records[3].kind, RecordKind::Close);
+/// ```
+pub struct RecordedStream<'a, W> {
+    stream: Stream,
+    recorder: &'a Recorder<W>,
+    id: Option<u64>,
+}
+
+impl<W: Write> RecordedStream<'_, W> {
+    /// The ID of the stream in the capture, or `None` if the stream is not sampled.
+    pub fn id(&self) -> Option<u64> {
+        self.id
+    }
+
+    /// Write data to be scanned to the opened stream, and record it if the stream is sampled.
+    pub fn scan<D, F>(&self, data: D, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    where
+        D: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        match self.id {
+            Some(id) => {
+                let data = data.as_ref();
+                let mut matches = vec![];
+                let res = self
+                    .stream
+                    .scan(data, scratch, tee(&mut matches, unsafe { on_match_event.split() }));
+                let recorded = self
+                    .recorder
+                    .record(RecordKind::Stream, id, data, matches, is_terminated(&res));
+
+                res.and(recorded)
+            }
+            None => self.stream.scan(data, scratch, on_match_event),
+        }
+    }
+
+    /// Close the stream, and record the matches at the end of the data if the stream is sampled.
+    pub fn close<F>(self, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    where
+        F: MatchEventHandler,
+    {
+        match self.id {
+            Some(id) => {
+                let mut matches = vec![];
+                let res = self
+                    .stream
+                    .close(scratch, tee(&mut matches, unsafe { on_match_event.split() }));
+                let recorded = self
+                    .recorder
+                    .record(RecordKind::Close, id, &[], matches, is_terminated(&res));
+
+                res.and(recorded)
+            }
+            None => self.stream.close(scratch, on_match_event),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+
+    use super::*;
+
+    fn read_capture(capture: &[u8]) -> Vec<Record> {
+        CaptureReader::new(capture)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_record_block() {
+        let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let recorder = Recorder::new(vec![]).unwrap().with_sampling(2).with_max_record_len(8);
+        let mut matches = vec![];
+
+        for data in &["foo test bar", "test", "bar test foo"] {
+            recorder
+                .scan(&db, data, &s, |_, from, to, _| {
+                    matches.push(from..to);
+                    Matching::Continue
+                })
+                .unwrap();
+        }
+
+        assert_eq!(matches, vec![4..8, 0..4, 4..8]);
+
+        let records = read_capture(&recorder.into_inner().unwrap());
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].kind, RecordKind::Block);
+        assert_eq!(records[0].data, b"foo test");
+        assert_eq!(records[0].len, 12);
+        assert!(records[0].is_truncated());
+        assert_eq!(records[0].matches, vec![Match { id: 0, from: 4, to: 8 }]);
+        assert!(!records[0].terminated);
+        assert_eq!(records[1].data, b"bar test");
+
+        let recorder = Recorder::new(vec![]).unwrap();
+        let err = recorder.scan(&db, "test", &s, Matching::Terminate).unwrap_err();
+
+        assert_eq!(err.downcast_ref::<crate::Error>(), Some(&crate::Error::ScanTerminated));
+
+        let records = read_capture(&recorder.into_inner().unwrap());
+
+        assert_eq!(records[0].matches.len(), 1);
+        assert!(records[0].terminated);
+    }
+
+    #[test]
+    fn test_record_stream() {
+        let db: StreamingDatabase = "/test/L\n/bar$/L".parse().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let recorder = Recorder::new(vec![]).unwrap().with_sampling(2);
+
+        for _ in 0..2 {
+            let st = recorder.open_stream(&db).unwrap();
+
+            st.scan("foo te", &s, ()).unwrap();
+            st.scan("st bar", &s, ()).unwrap();
+            st.close(&s, ()).unwrap();
+        }
+
+        let records = read_capture(&recorder.into_inner().unwrap());
+
+        assert_eq!(
+            records
+                .iter()
+                .map(|r| (r.kind, r.stream, r.matches.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (RecordKind::Stream, 1, vec![]),
+                (RecordKind::Stream, 1, vec![Match { id: 0, from: 4, to: 8 }]),
+                (RecordKind::Close, 1, vec![Match { id: 1, from: 9, to: 12 }]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_max_bytes() {
+        let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let recorder = Recorder::new(vec![]).unwrap().with_max_bytes(64);
+
+        for _ in 0..4 {
+            recorder.scan(&db, "foo bar baz", &s, ()).unwrap();
+        }
+
+        assert!(recorder.is_full());
+        assert!(recorder.written() <= 64);
+        assert_eq!(read_capture(&recorder.into_inner().unwrap()).len(), 1);
+    }
+
+    #[test]
+    fn test_read_truncated_record() {
+        let mut capture = MAGIC.to_vec();
+
+        capture.push(RecordKind::Block as u8);
+        capture.push(0);
+        capture.extend_from_slice(&0u64.to_le_bytes());
+        capture.extend_from_slice(&u64::MAX.to_le_bytes());
+        capture.extend_from_slice(&u64::MAX.to_le_bytes());
+        capture.extend_from_slice(b"test");
+
+        let mut records = CaptureReader::new(&capture[..]).unwrap();
+
+        assert!(records.next().unwrap().is_err());
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+use anyhow::{bail, Result};
+
+use crate::common::{Block, DatabaseRef, Streaming};
+use crate::runtime::{CaptureReader, Match, Matching, Record, RecordKind, Scratch};
+
+/// The difference between the recorded and replayed matches of a record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayDiff {
+    /// The index of the record in the capture.
+    pub index: usize,
+    /// The kind of the scan call.
+    pub kind: RecordKind,
+    /// The ID of the recorded stream, or zero for a block scan.
+    pub stream: u64,
+    /// The recorded matches which are not reported by the replay.
+    pub missing: Vec<Match>,
+    /// The matches reported by the replay which are not recorded.
+    pub unexpected: Vec<Match>,
+}
+
+/// The result of replaying a capture.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// The number of replayed records.
+    pub records: usize,
+    /// The number of records which are replayed without being compared,
+    /// because their data (or the data of a previous record of the stream) was truncated,
+    /// or a previous record of the stream was terminated by the callback.
+    pub skipped: usize,
+    /// The records whose replayed matches differ from the recorded ones.
+    pub diffs: Vec<ReplayDiff>,
+}
+
+impl ReplayReport {
+    /// Returns `true` if all the compared records reported the recorded matches.
+    pub fn is_clean(&self) -> bool {
+        self.diffs.is_empty()
+    }
+}
+
+/// A runner which replays the scan calls of a capture written by a `Recorder`
+/// against (possibly different) databases, and diffs the matches with the recorded ones.
+///
+/// The matches recorded from a scan terminated by its callback are incomplete,
+/// the replay always scans the whole data but only compares the matches up to the last recorded one.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::{CaptureReader, Recorder, Replayer};
+/// let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let recorder = Recorder::new(vec![]).unwrap();
+///
+/// recorder.scan(&db, "foo test bar", &s, ()).unwrap();
+///
+/// let capture = recorder.into_inner().unwrap();
+/// let new_db: BlockDatabase = pattern! {"test|bar"; SOM_LEFTMOST}.build().unwrap();
+/// let report = Replayer::new()
+///     .block(&new_db)
+///     .run(CaptureReader::new(&capture[..]).unwrap())
+///     .unwrap();
+///
+/// assert_eq!(report.records, 1);
+/// assert_eq!(report.diffs[0].unexpected[0].range(), 9..12);
+/// ```
+#[derive(Default)]
+pub struct Replayer<'a> {
+    block: Option<&'a DatabaseRef<Block>>,
+    streaming: Option<&'a DatabaseRef<Streaming>>,
+}
+
+impl<'a> Replayer<'a> {
+    /// Constructs a replayer without any database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replay the block scans against the database.
+    pub fn block(&mut self, db: &'a DatabaseRef<Block>) -> &mut Self {
+        self.block = Some(db);
+        self
+    }
+
+    /// Replay the streams against the database.
+    pub fn streaming(&mut self, db: &'a DatabaseRef<Streaming>) -> &mut Self {
+        self.streaming = Some(db);
+        self
+    }
+
+    /// Replay the records of the capture in order.
+    pub fn run<R: Read>(&self, capture: CaptureReader<R>) -> Result<ReplayReport> {
+        let mut scratch: Option<Scratch> = None;
+        let mut streams = HashMap::new();
+        let mut tainted = HashSet::new();
+        let mut terminated = HashSet::new();
+        let mut report = ReplayReport::default();
+
+        let res = capture.enumerate().try_for_each(|(index, record)| -> Result<()> {
+            let record = record?;
+            let mut matches = vec![];
+            let on_match_event = |id, from, to, _| {
+                matches.push(Match { id, from, to });
+                Matching::Continue
+            };
+
+            match record.kind {
+                RecordKind::Block => {
+                    let db = match self.block {
+                        Some(db) => db,
+                        None => bail!("record #{} is a block scan, but there is no block database", index),
+                    };
+                    let s = alloc_scratch(&mut scratch, db)?;
+
+                    db.scan(&record.data, s, on_match_event)?;
+                }
+                RecordKind::Stream | RecordKind::Close => {
+                    let db = match self.streaming {
+                        Some(db) => db,
+                        None => bail!("record #{} is a stream scan, but there is no streaming database", index),
+                    };
+                    let s = alloc_scratch(&mut scratch, db)?;
+
+                    if !streams.contains_key(&record.stream) {
+                        streams.insert(record.stream, db.open_stream()?);
+                    }
+
+                    if record.kind == RecordKind::Stream {
+                        streams[&record.stream].scan(&record.data, s, on_match_event)?;
+                    } else if let Some(stream) = streams.remove(&record.stream) {
+                        stream.close(s, on_match_event)?;
+                    }
+                }
+            }
+
+            report.records += 1;
+
+            if record.is_truncated() {
+                tainted.insert((record.kind == RecordKind::Block, record.stream));
+            }
+
+            if tainted.contains(&(record.kind == RecordKind::Block, record.stream))
+                || (record.kind != RecordKind::Block && terminated.contains(&record.stream))
+            {
+                report.skipped += 1;
+
+                if record.kind != RecordKind::Stream {
+                    tainted.remove(&(record.kind == RecordKind::Block, record.stream));
+                }
+            } else if let Some(diff) = diff(index, &record, matches) {
+                report.diffs.push(diff);
+            }
+
+            match record.kind {
+                RecordKind::Stream if record.terminated => {
+                    terminated.insert(record.stream);
+                }
+                RecordKind::Close => {
+                    terminated.remove(&record.stream);
+                }
+                _ => {}
+            }
+
+            Ok(())
+        });
+
+        if let Some(s) = scratch.as_ref() {
+            for (_, stream) in streams.drain() {
+                let _ = stream.close(s, ());
+            }
+        }
+
+        res.map(|_| report)
+    }
+}
+
+fn alloc_scratch<'a, T>(scratch: &'a mut Option<Scratch>, db: &DatabaseRef<T>) -> Result<&'a Scratch> {
+    match scratch {
+        Some(s) => {
+            db.realloc_scratch(s)?;
+        }
+        None => *scratch = Some(db.alloc_scratch()?),
+    }
+
+    Ok(scratch.as_ref().expect("scratch"))
+}
+
+fn diff(index: usize, record: &Record, mut replayed: Vec<Match>) -> Option<ReplayDiff> {
+    let mut recorded = record.matches.clone();
+
+    recorded.sort_by_key(|m| (m.to, m.from, m.id));
+    replayed.sort_by_key(|m| (m.to, m.from, m.id));
+
+    if let Some(last) = recorded.last().filter(|_| record.terminated) {
+        let last = (last.to, last.from, last.id);
+
+        replayed.retain(|m| (m.to, m.from, m.id) <= last);
+    }
+
+    let mut missing = vec![];
+    let mut unexpected = vec![];
+    let mut recorded = recorded.into_iter().peekable();
+    let mut replayed = replayed.into_iter().peekable();
+
+    loop {
+        match (recorded.peek(), replayed.peek()) {
+            (Some(l), Some(r)) if l == r => {
+                recorded.next();
+                replayed.next();
+            }
+            (Some(l), Some(r)) if (l.to, l.from, l.id) < (r.to, r.from, r.id) => missing.extend(recorded.next()),
+            (Some(_), Some(_)) | (None, Some(_)) => unexpected.extend(replayed.next()),
+            (Some(_), None) => missing.extend(recorded.next()),
+            (None, None) => break,
+        }
+    }
+
+    if missing.is_empty() && unexpected.is_empty() {
+        None
+    } else {
+        Some(ReplayDiff {
+            index,
+            kind: record.kind,
+            stream: record.stream,
+            missing,
+            unexpected,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::Recorder;
+
+    use super::*;
+
+    #[test]
+    fn test_replay() {
+        let block_db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let stream_db: StreamingDatabase = "/test/L\n/bar$/L".parse().unwrap();
+        let s = block_db.alloc_scratch().unwrap();
+        let s2 = stream_db.alloc_scratch().unwrap();
+        let recorder = Recorder::new(vec![]).unwrap().with_max_record_len(16);
+
+        recorder.scan(&block_db, "foo test bar", &s, ()).unwrap();
+        recorder.scan(&block_db, "test bar test bar test", &s, ()).unwrap();
+
+        let st = recorder.open_stream(&stream_db).unwrap();
+
+        st.scan("foo te", &s2, ()).unwrap();
+        st.scan("st bar", &s2, ()).unwrap();
+        st.close(&s2, ()).unwrap();
+
+        let capture = recorder.into_inner().unwrap();
+
+        let report = Replayer::new()
+            .block(&block_db)
+            .streaming(&stream_db)
+            .run(CaptureReader::new(&capture[..]).unwrap())
+            .unwrap();
+
+        assert_eq!(report.records, 5);
+        assert_eq!(report.skipped, 1);
+        assert!(report.is_clean());
+
+        let new_block_db: BlockDatabase = pattern! {"bar"; SOM_LEFTMOST}.build().unwrap();
+        let new_stream_db: StreamingDatabase = "/test/L".parse().unwrap();
+
+        let report = Replayer::new()
+            .block(&new_block_db)
+            .streaming(&new_stream_db)
+            .run(CaptureReader::new(&capture[..]).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            report.diffs,
+            vec![
+                ReplayDiff {
+                    index: 0,
+                    kind: RecordKind::Block,
+                    stream: 0,
+                    missing: vec![Match { id: 0, from: 4, to: 8 }],
+                    unexpected: vec![Match { id: 0, from: 9, to: 12 }],
+                },
+                ReplayDiff {
+                    index: 4,
+                    kind: RecordKind::Close,
+                    stream: 1,
+                    missing: vec![Match { id: 1, from: 9, to: 12 }],
+                    unexpected: vec![],
+                },
+            ]
+        );
+
+        let err = Replayer::new()
+            .streaming(&stream_db)
+            .run(CaptureReader::new(&capture[..]).unwrap())
+            .unwrap_err();
+
+        assert!(err.to_string().contains("no block database"));
+    }
+
+    #[test]
+    fn test_replay_terminated() {
+        let block_db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let stream_db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = block_db.alloc_scratch().unwrap();
+        let s2 = stream_db.alloc_scratch().unwrap();
+        let recorder = Recorder::new(vec![]).unwrap();
+
+        recorder
+            .scan(&block_db, "test test", &s, Matching::Terminate)
+            .unwrap_err();
+
+        let st = recorder.open_stream(&stream_db).unwrap();
+
+        st.scan("test test", &s2, Matching::Terminate).unwrap_err();
+        let _ = st.scan("test", &s2, ());
+        let _ = st.close(&s2, ());
+
+        let capture = recorder.into_inner().unwrap();
+        let records = CaptureReader::new(&capture[..])
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert!(records[0].terminated);
+        assert_eq!(records[0].matches, vec![Match { id: 0, from: 0, to: 4 }]);
+
+        let report = Replayer::new()
+            .block(&block_db)
+            .streaming(&stream_db)
+            .run(CaptureReader::new(&capture[..]).unwrap())
+            .unwrap();
+
+        assert_eq!(report.records, 4);
+        assert_eq!(report.skipped, 2);
+        assert!(report.is_clean());
+    }
+}
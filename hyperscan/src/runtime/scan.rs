@@ -1,5 +1,8 @@
-use std::io::Read;
-use std::mem;
+use std::any::Any;
+use std::cell::RefCell;
+use std::io::{IoSlice, Read};
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
 
 use anyhow::Result;
@@ -9,7 +12,11 @@ use libc::c_uint;
 use crate::common::{Block, DatabaseRef, Streaming, Vectored};
 use crate::errors::AsResult;
 use crate::ffi;
-use crate::runtime::{split_closure, ScratchRef, StreamRef};
+use crate::runtime::{
+    stream::hint_scratch, vectored::ClearOnDrop, ScratchRef, StreamRef, VectoredBuffer, VectoredScannable,
+};
+
+const STACK_BLOCKS: usize = 16;
 
 /// Indicating whether or not matching should continue on the target data.
 #[repr(i32)]
@@ -27,6 +34,25 @@ impl Default for Matching {
     }
 }
 
+/// A match reported by the scanner.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Match {
+    /// The ID number of the expression that matched.
+    pub id: u32,
+    /// The offset of the first byte that matches the expression,
+    /// or zero if the expression was not compiled with `SOM_LEFTMOST`.
+    pub from: u64,
+    /// The offset after the last byte that matches the expression.
+    pub to: u64,
+}
+
+impl Match {
+    /// The range of the match in the scanned data.
+    pub fn range(&self) -> Range<u64> {
+        self.from..self.to
+    }
+}
+
 /// Definition of the match event callback function type.
 ///
 /// A callback function matching the defined type must be provided by the
@@ -79,10 +105,50 @@ where
     F: FnMut(u32, u64, u64, u32) -> Matching,
 {
     unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut libc::c_void) {
-        let (callback, userdata) = split_closure(self);
+        (Some(on_match_trampoline::<F>), self as *mut _ as *mut _)
+    }
+}
 
-        (Some(mem::transmute(callback)), userdata)
+thread_local! {
+    static PANIC: RefCell<Option<Box<dyn Any + Send>>> = RefCell::new(None);
+}
+
+/// The match event callback of a closure, the context is the closure itself.
+///
+/// A panic can't unwind across the FFI boundary, it's caught and terminates the scan,
+/// then resumed by `resume_panic` when the scan returns.
+unsafe extern "C" fn on_match_trampoline<F>(
+    id: libc::c_uint,
+    from: libc::c_ulonglong,
+    to: libc::c_ulonglong,
+    flags: libc::c_uint,
+    ctx: *mut libc::c_void,
+) -> libc::c_int
+where
+    F: FnMut(u32, u64, u64, u32) -> Matching,
+{
+    let callback = &mut *(ctx as *mut F);
+
+    match panic::catch_unwind(AssertUnwindSafe(|| callback(id, from, to, flags))) {
+        Ok(matching) => matching as libc::c_int,
+        Err(payload) => {
+            PANIC.with(|cell| *cell.borrow_mut() = Some(payload));
+
+            Matching::Terminate as libc::c_int
+        }
+    }
+}
+
+/// Resume the panic caught in a match event callback, it must be called after every call which may invoke it.
+///
+/// The close and reset calls report success even if the callback terminated the matching,
+/// so the panic is checked regardless of the result.
+pub(crate) fn resume_panic<T>(res: Result<T>) -> Result<T> {
+    if let Some(payload) = PANIC.with(|cell| cell.borrow_mut().take()) {
+        panic::resume_unwind(payload)
     }
+
+    res
 }
 
 impl DatabaseRef<Block> {
@@ -110,12 +176,15 @@ impl DatabaseRef<Block> {
         T: AsRef<[u8]>,
         F: MatchEventHandler,
     {
+        #[cfg(debug_assertions)]
+        self.validate_for_scan(scratch)?;
+
         let data = data.as_ref();
 
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_scan(
+            let res = ffi::hs_scan(
                 self.as_ptr(),
                 data.as_ptr() as *const i8,
                 data.len() as u32,
@@ -124,7 +193,9 @@ impl DatabaseRef<Block> {
                 callback,
                 userdata,
             )
-            .ok()
+            .ok();
+
+            resume_panic(res).map_err(|err| self.diagnose_scratch(scratch, err))
         }
     }
 }
@@ -150,41 +221,212 @@ impl DatabaseRef<Vectored> {
     ///
     /// assert_eq!(matches, vec![3..7]);
     /// ```
-    pub fn scan<I, T, F>(&self, data: I, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    pub fn scan<V, F>(&self, data: V, scratch: &ScratchRef, on_match_event: F) -> Result<()>
     where
-        I: IntoIterator<Item = T>,
-        T: AsRef<[u8]>,
+        V: VectoredScannable,
+        F: MatchEventHandler,
+    {
+        self.scan_with(data, &mut VectoredBuffer::new(), scratch, on_match_event)
+    }
+
+    /// The vectored regular expression scanner with a reusable buffer of the block pointers and lengths.
+    ///
+    /// The buffer grows to the largest number of blocks scanned with it,
+    /// so the following scans don't allocate memory.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// # use hyperscan::VectoredBuffer;
+    /// let db: VectoredDatabase = pattern!{"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut buf = VectoredBuffer::with_capacity(16);
+    /// let mut matches = vec![];
+    ///
+    /// for packet in &[["foo te", "st bar"], ["tes", "t foo"]] {
+    ///     db.scan_with(&packet[..], &mut buf, &s, |_, from, to, _| {
+    ///         matches.push(from..to);
+    ///         Matching::Continue
+    ///     }).unwrap();
+    /// }
+    ///
+    /// assert_eq!(matches, vec![4..8, 0..4]);
+    /// ```
+    pub fn scan_with<V, F>(
+        &self,
+        data: V,
+        buf: &mut VectoredBuffer,
+        scratch: &ScratchRef,
+        on_match_event: F,
+    ) -> Result<()>
+    where
+        V: VectoredScannable,
+        F: MatchEventHandler,
+    {
+        buf.clear();
+
+        let buf = ClearOnDrop(buf);
+
+        data.for_each_block(|block| buf.0.push(block));
+
+        self.scan_blocks(&buf.0.ptrs, &buf.0.lens, scratch, on_match_event)
+    }
+
+    /// The vectored regular expression scanner of the `IoSlice`s, e.g. the fragments of a packet.
+    ///
+    /// No memory is allocated when there are at most 16 slices.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::io::IoSlice;
+    /// # use hyperscan::prelude::*;
+    /// let db: VectoredDatabase = pattern!{"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut matches = vec![];
+    ///
+    /// db.scan_io_slices(&[IoSlice::new(b"foo"), IoSlice::new(b"test")], &s, |_, from, to, _| {
+    ///     matches.push(from..to);
+    ///     Matching::Continue
+    /// }).unwrap();
+    ///
+    /// assert_eq!(matches, vec![3..7]);
+    /// ```
+    pub fn scan_io_slices<F>(&self, slices: &[IoSlice<'_>], scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        F: MatchEventHandler,
+    {
+        if slices.len() > STACK_BLOCKS {
+            return self.scan(slices, scratch, on_match_event);
+        }
+
+        let mut ptrs = [ptr::null(); STACK_BLOCKS];
+        let mut lens = [0; STACK_BLOCKS];
+
+        for (i, slice) in slices.iter().enumerate() {
+            ptrs[i] = slice.as_ptr() as *const i8;
+            lens[i] = slice.len() as c_uint;
+        }
+
+        self.scan_blocks(&ptrs[..slices.len()], &lens[..slices.len()], scratch, on_match_event)
+    }
+
+    /// The vectored regular expression scanner of the data blocks yielded by an iterator.
+    ///
+    /// The blocks are collected before scanning, so the iterator may yield owned blocks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: VectoredDatabase = pattern!{"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut matches = vec![];
+    ///
+    /// db.scan_iter(vec!["foo", "te", "st"].into_iter().map(String::from), &s, |_, from, to, _| {
+    ///     matches.push(from..to);
+    ///     Matching::Continue
+    /// }).unwrap();
+    ///
+    /// assert_eq!(matches, vec![3..7]);
+    /// ```
+    pub fn scan_iter<I, F>(&self, data: I, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        let blocks = data.into_iter().collect::<Vec<_>>();
+        let mut buf = VectoredBuffer::with_capacity(blocks.len());
+
+        blocks.iter().for_each(|block| buf.push(block.as_ref()));
+
+        self.scan_blocks(&buf.ptrs, &buf.lens, scratch, on_match_event)
+    }
+
+    /// The vectored regular expression scanner of the chunks of a `bytes::Buf`, e.g. a chain of `Bytes`.
+    ///
+    /// The chunks are scanned in place when the buffer exposes all of them with `Buf::bytes_vectored`,
+    /// otherwise the remaining data is copied into a contiguous block.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use bytes::{buf::BufExt, Bytes};
+    /// # use hyperscan::prelude::*;
+    /// let db: VectoredDatabase = pattern!{"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut matches = vec![];
+    ///
+    /// db.scan_buf(Bytes::from("foo te").chain(Bytes::from("st bar")), &s, |_, from, to, _| {
+    ///     matches.push(from..to);
+    ///     Matching::Continue
+    /// }).unwrap();
+    ///
+    /// assert_eq!(matches, vec![4..8]);
+    /// ```
+    #[cfg(feature = "bytes")]
+    pub fn scan_buf<B, F>(&self, mut buf: B, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        B: bytes::Buf,
         F: MatchEventHandler,
     {
-        let (ptrs, lens): (Vec<_>, Vec<_>) = data
-            .into_iter()
-            .map(|buf| {
-                let buf = buf.as_ref();
+        let remaining = buf.remaining();
+        let mut slices = vec![IoSlice::new(&[]); STACK_BLOCKS];
 
-                (buf.as_ptr() as *const i8, buf.len() as c_uint)
-            })
-            .unzip();
+        loop {
+            let n = buf.bytes_vectored(&mut slices);
+
+            if slices[..n].iter().map(|slice| slice.len()).sum::<usize>() == remaining {
+                return self.scan_io_slices(&slices[..n], scratch, on_match_event);
+            }
+
+            if n < slices.len() {
+                break;
+            }
+
+            slices.resize(slices.len() * 2, IoSlice::new(&[]));
+        }
+
+        let data = buf.to_bytes();
+
+        self.scan_io_slices(&[IoSlice::new(&data)], scratch, on_match_event)
+    }
+
+    fn scan_blocks<F>(
+        &self,
+        ptrs: &[*const i8],
+        lens: &[c_uint],
+        scratch: &ScratchRef,
+        mut on_match_event: F,
+    ) -> Result<()>
+    where
+        F: MatchEventHandler,
+    {
+        #[cfg(debug_assertions)]
+        self.validate_for_scan(scratch)?;
 
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_scan_vector(
+            let res = ffi::hs_scan_vector(
                 self.as_ptr(),
-                ptrs.as_slice().as_ptr() as *const *const i8,
-                lens.as_slice().as_ptr() as *const _,
+                ptrs.as_ptr(),
+                lens.as_ptr(),
                 ptrs.len() as u32,
                 0,
                 scratch.as_ptr(),
                 callback,
                 userdata,
             )
-            .ok()
+            .ok();
+
+            resume_panic(res).map_err(|err| self.diagnose_scratch(scratch, err))
         }
     }
 }
 
-const SCAN_BUF_SIZE: usize = 4096;
-
 impl DatabaseRef<Streaming> {
     /// Pattern matching takes place for stream-mode pattern databases.
     ///
@@ -212,25 +454,12 @@ impl DatabaseRef<Streaming> {
     ///
     /// assert_eq!(matches, vec![(4095, 4096), (4095, 4097), (4095, 4098)]);
     /// ```
-    pub fn scan<R, F>(&self, reader: &mut R, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    pub fn scan<R, F>(&self, reader: &mut R, scratch: &ScratchRef, on_match_event: F) -> Result<()>
     where
         R: Read,
         F: MatchEventHandler,
     {
-        let stream = self.open_stream()?;
-        let mut buf = [0; SCAN_BUF_SIZE];
-
-        let (callback, userdata) = unsafe { on_match_event.split() };
-
-        while let Ok(len) = reader.read(&mut buf[..]) {
-            if len == 0 {
-                break;
-            }
-
-            stream.scan(&buf[..len], scratch, (callback, userdata))?;
-        }
-
-        stream.close(scratch, (callback, userdata))
+        self.scan_reader(reader, scratch, on_match_event)
     }
 }
 
@@ -266,7 +495,18 @@ impl StreamRef {
     ///
     /// assert_eq!(matches, vec![(4, 8)]);
     /// ```
-    pub fn scan<T, F>(&self, data: T, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    pub fn scan<T, F>(&self, data: T, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        T: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        #[cfg(debug_assertions)]
+        self.validate(scratch)?;
+
+        self.scan_unchecked(data, scratch, on_match_event)
+    }
+
+    pub(crate) fn scan_unchecked<T, F>(&self, data: T, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
     where
         T: AsRef<[u8]>,
         F: MatchEventHandler,
@@ -276,7 +516,7 @@ impl StreamRef {
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_scan_stream(
+            let res = ffi::hs_scan_stream(
                 self.as_ptr(),
                 data.as_ptr() as *const i8,
                 data.len() as u32,
@@ -285,7 +525,85 @@ impl StreamRef {
                 callback,
                 userdata,
             )
-            .ok()
+            .ok();
+
+            resume_panic(res).map_err(hint_scratch)
         }
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use crate::prelude::*;
+    use crate::VectoredBuffer;
+
+    #[test]
+    fn test_panic_in_callback() {
+        let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let payload = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _ = db.scan("foo test bar", &s, |_, _, _, _| -> Matching { panic!("boom") });
+        }))
+        .unwrap_err();
+
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+
+        let mut matches = vec![];
+
+        db.scan("test", &s, |_, _, to, _| {
+            matches.push(to);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![4]);
+    }
+
+    #[test]
+    fn test_panic_in_vectored_callback() {
+        let db: VectoredDatabase = pattern! {"test"}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut buf = VectoredBuffer::new();
+
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            let _ = db.scan_with(vec!["foo te", "st bar"], &mut buf, &s, |_, _, _, _| -> Matching {
+                panic!("boom")
+            });
+        }))
+        .unwrap_err();
+
+        assert!(buf.ptrs.is_empty());
+        assert!(buf.lens.is_empty());
+    }
+
+    #[test]
+    fn test_panic_in_stream_close() {
+        let db: StreamingDatabase = pattern! {"test$"}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let st = db.open_stream().unwrap();
+
+        st.scan("foo test", &s, ()).unwrap();
+
+        let payload = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _ = st.close(&s, |_, _, _, _| -> Matching { panic!("boom") });
+        }))
+        .unwrap_err();
+
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+
+        let st = db.open_stream().unwrap();
+        let mut matches = vec![];
+
+        st.scan("test", &s, ()).unwrap();
+        st.close(&s, |_, _, to, _| {
+            matches.push(to);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![4]);
+    }
+}
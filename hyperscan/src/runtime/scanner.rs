@@ -0,0 +1,293 @@
+use std::sync::Mutex;
+#[cfg(feature = "histogram")]
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+use thread_local::ThreadLocal;
+
+use crate::common::{Block, Database, DatabaseRef, Streaming, Vectored};
+#[cfg(feature = "histogram")]
+use crate::runtime::LatencyHistogram;
+use crate::runtime::{MatchEventHandler, Scratch, ScratchRef, Stream, StreamRef, VectoredScannable};
+
+/// A thread-safe scanner which owns the database and manages a scratch space per thread.
+///
+/// The scratch space of a thread is lazily cloned from a prototype the first time the thread scans,
+/// and is reused by the following scans of that thread.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use std::thread;
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::Scanner;
+/// let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+/// let scanner = Arc::new(Scanner::new(db).unwrap());
+///
+/// let handles = (0..4)
+///     .map(|_| {
+///         let scanner = scanner.clone();
+///
+///         thread::spawn(move || {
+///             let mut matches = vec![];
+///
+///             scanner.scan("foo test bar", |_, from, to, _| {
+///                 matches.push(from..to);
+///                 Matching::Continue
+///             }).unwrap();
+///
+///             matches
+///         })
+///     })
+///     .collect::<Vec<_>>();
+///
+/// for handle in handles {
+///     assert_eq!(handle.join().unwrap(), vec![4..8]);
+/// }
+/// ```
+pub struct Scanner<T> {
+    db: Database<T>,
+    prototype: Mutex<Scratch>,
+    scratches: ThreadLocal<Scratch>,
+    #[cfg(feature = "histogram")]
+    latency: Option<LatencyHistogram>,
+}
+
+impl<T> Scanner<T> {
+    /// Constructs a scanner which owns the database.
+    pub fn new(db: Database<T>) -> Result<Self> {
+        let prototype = Mutex::new(db.alloc_scratch()?);
+
+        Ok(Scanner {
+            db,
+            prototype,
+            scratches: ThreadLocal::new(),
+            #[cfg(feature = "histogram")]
+            latency: None,
+        })
+    }
+
+    /// Record the duration of every scan call of the scanner into a latency histogram.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// # use hyperscan::Scanner;
+    /// let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let scanner = Scanner::new(db).unwrap().with_latency_histogram();
+    ///
+    /// scanner.scan("foo test bar", ()).unwrap();
+    ///
+    /// let latency = scanner.latency().unwrap().snapshot();
+    ///
+    /// assert_eq!(latency.count(), 1);
+    /// assert!(latency.p99() <= latency.max());
+    /// ```
+    #[cfg(feature = "histogram")]
+    pub fn with_latency_histogram(mut self) -> Self {
+        self.latency = Some(LatencyHistogram::new());
+        self
+    }
+
+    /// The latency histogram of the scan calls, if enabled.
+    #[cfg(feature = "histogram")]
+    pub fn latency(&self) -> Option<&LatencyHistogram> {
+        self.latency.as_ref()
+    }
+
+    #[cfg(feature = "histogram")]
+    fn timed<R>(&self, f: impl FnOnce() -> R) -> R {
+        match self.latency {
+            Some(ref latency) => {
+                let now = Instant::now();
+                let res = f();
+
+                latency.record(now.elapsed());
+
+                res
+            }
+            None => f(),
+        }
+    }
+
+    #[cfg(not(feature = "histogram"))]
+    #[inline(always)]
+    fn timed<R>(&self, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    /// The database of the scanner.
+    pub fn database(&self) -> &DatabaseRef<T> {
+        &self.db
+    }
+
+    /// Consumes the scanner, returning the database.
+    pub fn into_inner(self) -> Database<T> {
+        self.db
+    }
+
+    /// The scratch space of the current thread, it's cloned from the prototype if not exists.
+    ///
+    /// The scratch space can't be used by the match callback of a scan with it.
+    pub fn scratch(&self) -> Result<&ScratchRef> {
+        self.scratches
+            .get_or_try(|| {
+                self.prototype
+                    .lock()
+                    .map_err(|_| anyhow!("scratch prototype poisoned"))?
+                    .try_clone()
+            })
+            .map(|s| &**s)
+    }
+}
+
+impl Scanner<Block> {
+    /// The block (non-streaming) regular expression scanner with the scratch space of the current thread.
+    pub fn scan<D, F>(&self, data: D, on_match_event: F) -> Result<()>
+    where
+        D: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        let scratch = self.scratch()?;
+
+        self.timed(|| self.db.scan(data, scratch, on_match_event))
+    }
+}
+
+impl Scanner<Vectored> {
+    /// The vectored regular expression scanner with the scratch space of the current thread.
+    pub fn scan<V, F>(&self, data: V, on_match_event: F) -> Result<()>
+    where
+        V: VectoredScannable,
+        F: MatchEventHandler,
+    {
+        let scratch = self.scratch()?;
+
+        self.timed(|| self.db.scan(data, scratch, on_match_event))
+    }
+}
+
+impl Scanner<Streaming> {
+    /// Open and initialise a stream of the database.
+    pub fn open_stream(&self) -> Result<Stream> {
+        self.db.open_stream()
+    }
+
+    /// Write data to be scanned to the opened stream with the scratch space of the current thread.
+    pub fn scan_stream<D, F>(&self, stream: &StreamRef, data: D, on_match_event: F) -> Result<()>
+    where
+        D: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        let scratch = self.scratch()?;
+
+        self.timed(|| stream.scan(data, scratch, on_match_event))
+    }
+
+    /// Close a stream with the scratch space of the current thread.
+    pub fn close_stream<F>(&self, stream: Stream, on_match_event: F) -> Result<()>
+    where
+        F: MatchEventHandler,
+    {
+        let scratch = self.scratch()?;
+
+        self.timed(|| stream.close(scratch, on_match_event))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::prelude::*;
+
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_scanner_is_send_sync() {
+        assert_send_sync::<Scanner<Block>>();
+        assert_send_sync::<Scanner<Streaming>>();
+        assert_send_sync::<Scanner<Vectored>>();
+    }
+
+    #[test]
+    fn test_scanner() {
+        let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let scanner = Arc::new(Scanner::new(db).unwrap());
+
+        let handles = (0..4)
+            .map(|i| {
+                let scanner = scanner.clone();
+
+                thread::spawn(move || {
+                    let mut matches = vec![];
+
+                    for _ in 0..i + 1 {
+                        scanner
+                            .scan("foo test bar", |_, from, to, _| {
+                                matches.push(from..to);
+                                Matching::Continue
+                            })
+                            .unwrap();
+                    }
+
+                    matches
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.join().unwrap(), vec![4..8; i + 1]);
+        }
+    }
+
+    #[test]
+    fn test_stream_scanner() {
+        let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let scanner = Scanner::new(db).unwrap();
+        let st = scanner.open_stream().unwrap();
+        let mut matches = vec![];
+
+        for data in &["foo t", "es", "t bar"] {
+            scanner
+                .scan_stream(&st, data, |_, from, to, _| {
+                    matches.push(from..to);
+                    Matching::Continue
+                })
+                .unwrap();
+        }
+
+        scanner.close_stream(st, Matching::Continue).unwrap();
+
+        assert_eq!(matches, vec![4..8]);
+    }
+
+    #[cfg(feature = "histogram")]
+    #[test]
+    fn test_scanner_latency() {
+        let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let scanner = Scanner::new(db).unwrap();
+
+        assert!(scanner.latency().is_none());
+
+        let scanner = scanner.with_latency_histogram();
+        let st = scanner.open_stream().unwrap();
+
+        for data in &["foo t", "es", "t bar"] {
+            scanner.scan_stream(&st, data, ()).unwrap();
+        }
+
+        scanner.close_stream(st, ()).unwrap();
+
+        let latency = scanner.latency().unwrap().snapshot();
+
+        assert_eq!(latency.count(), 4);
+        assert!(latency.percentile(0.0) <= latency.p50());
+        assert!(latency.p999() <= latency.max());
+    }
+}
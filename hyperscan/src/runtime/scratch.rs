@@ -5,12 +5,14 @@ use anyhow::Result;
 use foreign_types::{foreign_type, ForeignType, ForeignTypeRef};
 
 use crate::common::DatabaseRef;
-use crate::errors::AsResult;
+use crate::errors::{AsResult, Error};
 use crate::ffi;
 
 foreign_type! {
     /// A large enough region of scratch space to support a given database.
-    pub unsafe type Scratch {
+    ///
+    /// The scratch space can be moved to another thread, but can't be used by more than one thread at a time.
+    pub unsafe type Scratch: Send {
         type CType = ffi::hs_scratch_t;
 
         fn drop = free_scratch;
@@ -50,12 +52,34 @@ impl Scratch {
 }
 
 impl ScratchRef {
+    /// Allocate a scratch space that is a clone of an existing scratch space.
+    ///
+    /// This is useful when multiple concurrent threads will be using the same set of compiled databases,
+    /// and another scratch space is required.
+    pub fn try_clone(&self) -> Result<Scratch> {
+        let mut p = MaybeUninit::uninit();
+
+        unsafe { ffi::hs_clone_scratch(self.as_ptr(), p.as_mut_ptr()).map(|_| Scratch::from_ptr(p.assume_init())) }
+    }
+
     /// Provides the size of the given scratch space.
     pub fn size(&self) -> Result<usize> {
         let mut size = MaybeUninit::uninit();
 
         unsafe { ffi::hs_scratch_size(self.as_ptr(), size.as_mut_ptr()).map(|_| size.assume_init()) }
     }
+
+    /// Returns `true` if the scratch space was allocated (or grown) for the database.
+    ///
+    /// The scratch space is cloned and grown for the database, it's too expensive for the hot path.
+    pub fn is_allocated_for<T>(&self, db: &DatabaseRef<T>) -> Result<bool> {
+        let mut s = self.try_clone()?;
+        let size = s.size()?;
+
+        unsafe { s.realloc(db)? };
+
+        Ok(s.size()? == size)
+    }
 }
 
 impl<T> DatabaseRef<T> {
@@ -64,6 +88,40 @@ impl<T> DatabaseRef<T> {
         unsafe { Scratch::alloc(self) }
     }
 
+    /// Check the scratch space was allocated (or grown) for the database.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+    /// let other: VectoredDatabase = pattern! {"foobar"}.build().unwrap();
+    /// let mut s = db.alloc_scratch().unwrap();
+    ///
+    /// assert!(db.validate_scratch(&s).is_ok());
+    /// assert!(other.validate_scratch(&s).is_err());
+    ///
+    /// other.realloc_scratch(&mut s).unwrap();
+    ///
+    /// assert!(other.validate_scratch(&s).is_ok());
+    /// ```
+    pub fn validate_scratch(&self, scratch: &ScratchRef) -> Result<()> {
+        if scratch.is_allocated_for(self)? {
+            Ok(())
+        } else {
+            Err(Error::ScratchMismatch.into())
+        }
+    }
+
+    /// Replace an `Error::Invalid` returned by a scan with `Error::ScratchMismatch`
+    /// if the scratch space was not allocated for the database.
+    pub(crate) fn diagnose_scratch(&self, scratch: &ScratchRef, err: anyhow::Error) -> anyhow::Error {
+        match err.downcast_ref::<Error>() {
+            Some(Error::Invalid) if !scratch.is_allocated_for(self).unwrap_or(true) => Error::ScratchMismatch.into(),
+            _ => err,
+        }
+    }
+
     /// Reallocate a "scratch" space for use by Hyperscan.
     pub fn realloc_scratch<'a>(&'a self, s: &'a mut Scratch) -> Result<&'a mut Scratch> {
         unsafe { s.realloc(self) }.map(|_| s)
@@ -85,6 +143,7 @@ impl<T> DatabaseRef<T> {
 #[cfg(test)]
 pub mod tests {
     use crate::prelude::*;
+    use crate::Error;
 
     const SCRATCH_SIZE: usize = 2000;
 
@@ -106,4 +165,19 @@ pub mod tests {
 
         assert!(s2.size().unwrap() > s.size().unwrap());
     }
+
+    #[test]
+    fn test_scratch_mismatch() {
+        let db: BlockDatabase = "test".parse().unwrap();
+        let db2: VectoredDatabase = "foobar".parse().unwrap();
+
+        let s = db.alloc_scratch().unwrap();
+
+        assert!(s.is_allocated_for(&db).unwrap());
+        assert!(!s.is_allocated_for(&db2).unwrap());
+
+        let err = db2.scan(vec!["foo", "bar"], &s, Matching::Continue).unwrap_err();
+
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ScratchMismatch));
+    }
 }
@@ -0,0 +1,301 @@
+use std::fs::File;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use memmap::{Mmap, MmapOptions};
+
+use crate::common::{DatabaseRef, Vectored};
+use crate::runtime::{Matching, ScratchRef, VectoredScannable};
+
+/// The longest block a vectored scan accepts, longer segments are scanned as several blocks.
+const MAX_BLOCK_LEN: usize = u32::MAX as usize;
+
+/// A memory-mapped extent of a file.
+#[derive(Debug)]
+pub struct Segment {
+    path: PathBuf,
+    offset: u64,
+    start: u64,
+    map: Option<Mmap>,
+}
+
+impl Segment {
+    /// The path of the mapped file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The offset of the extent in the file.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The range of the extent in the logical input.
+    pub fn range(&self) -> Range<u64> {
+        self.start..self.start + self.len() as u64
+    }
+
+    /// The length of the extent.
+    pub fn len(&self) -> usize {
+        self.map.as_ref().map_or(0, |map| map.len())
+    }
+
+    /// Returns `true` if the extent is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The mapped data of the extent.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.map.as_ref().map_or(&[][..], |map| &map[..])
+    }
+}
+
+/// A match reported by scanning the segments.
+#[derive(Clone, Copy, Debug)]
+pub struct SegmentMatch<'a> {
+    /// The ID number of the expression that matched.
+    pub id: u32,
+    /// The offset of the first byte that matches the expression in the logical input.
+    pub from: u64,
+    /// The offset after the last byte that matches the expression in the logical input.
+    pub to: u64,
+    /// The index of the segment which contains the last byte of the match.
+    pub index: usize,
+    /// The segment which contains the last byte of the match.
+    pub segment: &'a Segment,
+}
+
+impl SegmentMatch<'_> {
+    /// The offset after the last byte of the match in the file of the segment.
+    pub fn file_offset(&self) -> u64 {
+        self.segment.offset + (self.to - self.segment.start)
+    }
+}
+
+/// Several memory-mapped files or extents of files, scanned as a single logical input.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::Segments;
+/// let db: VectoredDatabase = pattern! {"secret"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+///
+/// let mut segments = Segments::new();
+///
+/// segments.map_file("upload.part1").unwrap();
+/// segments.map_file("upload.part2").unwrap();
+///
+/// db.scan_segments(&segments, &s, |m| {
+///     println!("found {} @ {}:{}", m.id, m.segment.path().display(), m.file_offset());
+///
+///     Matching::Continue
+/// })
+/// .unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct Segments {
+    segments: Vec<Segment>,
+    len: u64,
+}
+
+impl Segments {
+    /// Constructs an empty set of segments.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map the whole file as the next segment.
+    pub fn map_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self> {
+        let path = path.as_ref();
+        let len = path
+            .metadata()
+            .with_context(|| format!("stat file {}", path.display()))?
+            .len();
+
+        self.map_extent(path, 0, len as usize)
+    }
+
+    /// Map an extent of the file as the next segment.
+    ///
+    /// The extent must be within the file, since touching a page mapped past its end raises `SIGBUS`.
+    pub fn map_extent<P: AsRef<Path>>(&mut self, path: P, offset: u64, len: usize) -> Result<&mut Self> {
+        let path = path.as_ref();
+        let map = if len == 0 {
+            None
+        } else {
+            let file = File::open(path).with_context(|| format!("open file {}", path.display()))?;
+            let file_len = file
+                .metadata()
+                .with_context(|| format!("stat file {}", path.display()))?
+                .len();
+
+            match offset.checked_add(len as u64) {
+                Some(end) if end <= file_len => {}
+                _ => bail!(
+                    "extent {}+{} is past the end of file {} ({} bytes)",
+                    offset,
+                    len,
+                    path.display(),
+                    file_len
+                ),
+            }
+
+            Some(unsafe {
+                MmapOptions::new()
+                    .offset(offset)
+                    .len(len)
+                    .map(&file)
+                    .with_context(|| format!("map file {} @ {}..{}", path.display(), offset, offset + len as u64))?
+            })
+        };
+
+        self.segments.push(Segment {
+            path: path.to_owned(),
+            offset,
+            start: self.len,
+            map,
+        });
+        self.len += len as u64;
+
+        Ok(self)
+    }
+
+    /// The mapped segments in order.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// The total length of the logical input.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the logical input is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Locate the segment which contains the byte before the offset of the logical input,
+    /// that is, the segment a match ending at the offset belongs to.
+    pub fn locate(&self, offset: u64) -> Option<(usize, &Segment)> {
+        let last = offset.saturating_sub(1);
+        let index = self
+            .segments
+            .partition_point(|segment| segment.start <= last)
+            .checked_sub(1)?;
+        let segment = &self.segments[index];
+
+        if segment.range().contains(&last) {
+            Some((index, segment))
+        } else {
+            None
+        }
+    }
+}
+
+impl VectoredScannable for Segments {
+    fn for_each_block<'a, F: FnMut(&'a [u8])>(&'a self, f: F) {
+        self.segments
+            .iter()
+            .flat_map(|segment| segment.as_bytes().chunks(MAX_BLOCK_LEN))
+            .for_each(f)
+    }
+}
+
+impl DatabaseRef<Vectored> {
+    /// Scan the segments as a single logical input, attributing each match to the segment it ends in.
+    pub fn scan_segments<'a, F>(&self, segments: &'a Segments, scratch: &ScratchRef, mut on_match: F) -> Result<()>
+    where
+        F: FnMut(SegmentMatch<'a>) -> Matching,
+    {
+        self.scan(segments, scratch, |id, from, to, _| match segments.locate(to) {
+            Some((index, segment)) => on_match(SegmentMatch {
+                id,
+                from,
+                to,
+                index,
+                segment,
+            }),
+            None => Matching::Continue,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::env;
+    use std::fs;
+
+    use crate::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_scan_segments() {
+        let dir = env::temp_dir().join(format!("hyperscan-segments-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let part1 = dir.join("part1");
+        let part2 = dir.join("part2");
+        let empty = dir.join("empty");
+
+        fs::write(&part1, "header foo te").unwrap();
+        fs::write(&part2, "xxst bar test").unwrap();
+        fs::write(&empty, "").unwrap();
+
+        let mut segments = Segments::new();
+
+        segments
+            .map_file(&part1)
+            .unwrap()
+            .map_file(&empty)
+            .unwrap()
+            .map_extent(&part2, 2, 11)
+            .unwrap();
+
+        assert_eq!(segments.len(), 24);
+        assert_eq!(segments.segments().len(), 3);
+
+        let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan_segments(&segments, &s, |m| {
+            matches.push((m.from..m.to, m.index, m.file_offset()));
+            Matching::Continue
+        })
+        .unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(matches, vec![(11..15, 2, 4), (20..24, 2, 13)]);
+
+        assert_eq!(segments.locate(1).map(|(index, _)| index), Some(0));
+        assert_eq!(segments.locate(13).map(|(index, _)| index), Some(0));
+        assert_eq!(segments.locate(14).map(|(index, _)| index), Some(2));
+        assert_eq!(segments.locate(25).map(|(index, _)| index), None);
+    }
+
+    #[test]
+    fn test_map_extent_past_eof() {
+        let path = env::temp_dir().join(format!("hyperscan-segments-eof-{}", std::process::id()));
+
+        fs::write(&path, "test").unwrap();
+
+        let mut segments = Segments::new();
+
+        assert!(segments.map_extent(&path, 0, 4).is_ok());
+        assert!(segments.map_extent(&path, 2, 4).is_err());
+        assert!(segments.map_extent(&path, 8, 1).is_err());
+        assert!(segments.map_extent(&path, u64::max_value(), 1).is_err());
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(segments.segments().len(), 1);
+        assert_eq!(segments.len(), 4);
+    }
+}
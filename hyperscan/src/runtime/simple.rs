@@ -0,0 +1,145 @@
+use std::cell::RefCell;
+
+use anyhow::Result;
+
+use crate::common::{Block, DatabaseRef, Vectored};
+use crate::runtime::{Match, Matching, Scratch, ScratchRef, VectoredScannable};
+
+thread_local! {
+    static SCRATCH: RefCell<Option<Scratch>> = RefCell::new(None);
+}
+
+/// Run the closure with the scratch space of the current thread, grown to support the database.
+///
+/// A temporary scratch space is allocated if the scratch space of the thread is in use,
+/// e.g. when a simple scan is started from the match callback of another one.
+fn with_thread_scratch<T, F, R>(db: &DatabaseRef<T>, f: F) -> Result<R>
+where
+    F: FnOnce(&ScratchRef) -> Result<R>,
+{
+    SCRATCH.with(|cell| match cell.try_borrow_mut() {
+        Ok(mut slot) => {
+            let s = match slot.as_mut() {
+                Some(s) => db.realloc_scratch(s)?,
+                None => slot.get_or_insert(db.alloc_scratch()?),
+            };
+
+            f(s)
+        }
+        Err(_) => {
+            let s = db.alloc_scratch()?;
+
+            f(&s)
+        }
+    })
+}
+
+impl DatabaseRef<Block> {
+    /// Scan the data without a scratch space, returning all the matches.
+    ///
+    /// The scratch space is lazily allocated per thread and grown to support every database
+    /// scanned from that thread, it is kept until the thread exits.
+    /// Each thread which calls this method pays for one allocation the first time,
+    /// and holds a scratch space as large as required by the largest database it scanned.
+    ///
+    /// It's convenient for low-rate callers, use `DatabaseRef::scan` with a dedicated scratch space
+    /// when the matches should be handled without being collected, or the scan should be terminated early.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let matches = db.scan_simple("foo test bar").unwrap();
+    ///
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!(matches[0].range(), 4..8);
+    /// ```
+    pub fn scan_simple<T: AsRef<[u8]>>(&self, data: T) -> Result<Vec<Match>> {
+        with_thread_scratch(self, |s| {
+            let mut matches = vec![];
+
+            self.scan(data, s, |id, from, to, _| {
+                matches.push(Match { id, from, to });
+                Matching::Continue
+            })
+            .map(|_| matches)
+        })
+    }
+}
+
+impl DatabaseRef<Vectored> {
+    /// Scan the vectored data without a scratch space, returning all the matches.
+    ///
+    /// The scratch space is lazily allocated and cached per thread, see `DatabaseRef<Block>::scan_simple`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let matches = db.scan_simple(vec!["foo te", "st bar"]).unwrap();
+    ///
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!(matches[0].range(), 4..8);
+    /// ```
+    pub fn scan_simple<V: VectoredScannable>(&self, data: V) -> Result<Vec<Match>> {
+        with_thread_scratch(self, |s| {
+            let mut matches = vec![];
+
+            self.scan(data, s, |id, from, to, _| {
+                matches.push(Match { id, from, to });
+                Matching::Continue
+            })
+            .map(|_| matches)
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_scan_simple() {
+        let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let other: BlockDatabase = "/foo/\n/bar/".parse().unwrap();
+
+        assert_eq!(
+            db.scan_simple("foo test bar").unwrap(),
+            vec![Match { id: 0, from: 4, to: 8 }]
+        );
+        assert_eq!(
+            other.scan_simple("foo test bar").unwrap(),
+            vec![Match { id: 0, from: 0, to: 3 }, Match { id: 1, from: 0, to: 12 }]
+        );
+        assert!(db.scan_simple("nothing").unwrap().is_empty());
+
+        let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+
+        assert_eq!(
+            db.scan_simple(vec!["te", "st"]).unwrap(),
+            vec![Match { id: 0, from: 0, to: 4 }]
+        );
+    }
+
+    #[test]
+    fn test_scan_simple_reentrant() {
+        let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+        let other: BlockDatabase = pattern! {"foo"}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        with_thread_scratch(&db, |_| {
+            db.scan("foo test", &s, |_, _, _, _| {
+                matches.extend(other.scan_simple("foo").unwrap());
+                Matching::Continue
+            })
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![Match { id: 0, from: 0, to: 3 }]);
+    }
+}
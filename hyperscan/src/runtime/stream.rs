@@ -1,12 +1,13 @@
 use std::mem::MaybeUninit;
+use std::ptr;
 
 use anyhow::Result;
 use foreign_types::{foreign_type, ForeignType, ForeignTypeRef};
 
 use crate::common::{DatabaseRef, Streaming};
-use crate::errors::AsResult;
+use crate::errors::{AsResult, Error};
 use crate::ffi;
-use crate::runtime::{MatchEventHandler, ScratchRef};
+use crate::runtime::{scan::resume_panic, MatchEventHandler, ScratchRef};
 
 impl DatabaseRef<Streaming> {
     /// Provides the size of the stream state allocated by a single stream opened against the given database.
@@ -24,9 +25,22 @@ impl DatabaseRef<Streaming> {
     }
 }
 
+/// Add a hint to an `Error::Invalid` returned by a stream operation with a scratch space.
+///
+/// The database of a stream is unknown, the scratch space is the most likely cause,
+/// which must be allocated (or grown) for the database the stream was opened from.
+pub(crate) fn hint_scratch(err: anyhow::Error) -> anyhow::Error {
+    match err.downcast_ref::<Error>() {
+        Some(Error::Invalid) => err.context("the scratch space may not be allocated for the database of the stream"),
+        _ => err,
+    }
+}
+
 foreign_type! {
     /// A pattern matching state can be maintained across multiple blocks of target data
-    pub unsafe type Stream {
+    ///
+    /// The stream can be moved to another thread, but can't be used by more than one thread at a time.
+    pub unsafe type Stream: Send {
         type CType = ffi::hs_stream_t;
 
         fn drop = drop_stream;
@@ -98,7 +112,90 @@ impl StreamRef {
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_reset_stream(self.as_ptr(), 0, scratch.as_ptr(), callback, userdata).ok()
+            let res = ffi::hs_reset_stream(self.as_ptr(), 0, scratch.as_ptr(), callback, userdata).ok();
+
+            resume_panic(res).map_err(hint_scratch)
+        }
+    }
+
+    /// Duplicate the given stream.
+    ///
+    /// The new stream will have the same state as the original including the current stream offset.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    ///
+    /// let s = db.alloc_scratch().unwrap();
+    /// let st = db.open_stream().unwrap();
+    ///
+    /// let mut matches = vec![];
+    ///
+    /// let mut callback = |_, from, to, _| {
+    ///     matches.push((from, to));
+    ///
+    ///     Matching::Continue
+    /// };
+    ///
+    /// st.scan("foo t", &s, &mut callback).unwrap();
+    ///
+    /// let st2 = st.try_clone().unwrap();
+    ///
+    /// st.scan("est", &s, &mut callback).unwrap();
+    /// st2.scan("ext", &s, &mut callback).unwrap();
+    ///
+    /// st.close(&s, &mut callback).unwrap();
+    /// st2.close(&s, &mut callback).unwrap();
+    ///
+    /// assert_eq!(matches, vec![(4, 8)]);
+    /// ```
+    pub fn try_clone(&self) -> Result<Stream> {
+        let mut p = MaybeUninit::uninit();
+
+        unsafe { ffi::hs_copy_stream(p.as_mut_ptr(), self.as_ptr()).map(|_| Stream::from_ptr(p.assume_init())) }
+    }
+
+    /// Duplicate the given `from` stream state onto the stream, discarding any EOD matches of the stream.
+    ///
+    /// This allows a long-lived stream to be reused across sessions without reallocating the stream state.
+    ///
+    /// Note: the stream and the `from` stream must be open against the same database.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"test$"; SOM_LEFTMOST}.build().unwrap();
+    ///
+    /// let s = db.alloc_scratch().unwrap();
+    /// let st = db.open_stream().unwrap();
+    ///
+    /// let mut matches = vec![];
+    ///
+    /// let mut callback = |_, from, to, _| {
+    ///     matches.push((from, to));
+    ///
+    ///     Matching::Continue
+    /// };
+    ///
+    /// st.scan("foo t", &s, &mut callback).unwrap();
+    ///
+    /// let st2 = db.open_stream().unwrap();
+    ///
+    /// st2.scan("test", &s, &mut callback).unwrap();
+    /// st2.reset_and_copy_from(&st).unwrap();
+    /// st2.scan("est", &s, &mut callback).unwrap();
+    /// st2.close(&s, &mut callback).unwrap();
+    ///
+    /// st.close(&s, Matching::Terminate).unwrap();
+    ///
+    /// assert_eq!(matches, vec![(4, 8)]);
+    /// ```
+    pub fn reset_and_copy_from(&self, from: &StreamRef) -> Result<()> {
+        unsafe {
+            ffi::hs_reset_and_copy_stream(self.as_ptr(), from.as_ptr(), ptr::null_mut(), None, ptr::null_mut()).ok()
         }
     }
 
@@ -146,11 +243,53 @@ impl StreamRef {
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_reset_and_copy_stream(self.as_ptr(), from.as_ptr(), scratch.as_ptr(), callback, userdata).ok()
+            let res =
+                ffi::hs_reset_and_copy_stream(self.as_ptr(), from.as_ptr(), scratch.as_ptr(), callback, userdata).ok();
+
+            resume_panic(res).map_err(hint_scratch)
         }
     }
 }
 
+impl StreamRef {
+    /// Report the end of data matches of the stream without closing it.
+    ///
+    /// The matches are produced by closing a copy of the stream,
+    /// so expressions anchored to the end of data (for example, via the use of the `$` meta-character)
+    /// can be checked at a logical message boundary, while the stream keeps its state and can continue to be scanned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"test$"; SOM_LEFTMOST}.build().unwrap();
+    ///
+    /// let s = db.alloc_scratch().unwrap();
+    /// let st = db.open_stream().unwrap();
+    ///
+    /// let mut matches = vec![];
+    ///
+    /// let mut callback = |_, from, to, _| {
+    ///     matches.push((from, to));
+    ///
+    ///     Matching::Continue
+    /// };
+    ///
+    /// st.scan("foo test", &s, &mut callback).unwrap();
+    /// st.flush(&s, &mut callback).unwrap();
+    /// st.scan(" and test", &s, &mut callback).unwrap();
+    /// st.close(&s, &mut callback).unwrap();
+    ///
+    /// assert_eq!(matches, vec![(4, 8), (13, 17)]);
+    /// ```
+    pub fn flush<F>(&self, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        F: MatchEventHandler,
+    {
+        self.try_clone()?.close(scratch, on_match_event)
+    }
+}
+
 impl Stream {
     /// Close a stream.
     ///
@@ -167,7 +306,9 @@ impl Stream {
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_close_stream(self.as_ptr(), scratch.as_ptr(), callback, userdata).ok()
+            let res = ffi::hs_close_stream(self.as_ptr(), scratch.as_ptr(), callback, userdata).ok();
+
+            resume_panic(res).map_err(hint_scratch)
         }
     }
 }
@@ -178,6 +319,9 @@ impl StreamRef {
     /// This compressed representation can be converted back into a stream state by using `expand()`
     /// or `reset_and_expand()`.
     ///
+    /// Returns `Error::InsufficientSpace` if the buffer is too small,
+    /// use `compressed_size()` to find out the required size of the buffer.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -218,6 +362,80 @@ impl StreamRef {
         }
     }
 
+    /// Provides the size of the buffer required to hold the compressed representation of the stream.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    ///
+    /// let s = db.alloc_scratch().unwrap();
+    /// let st = db.open_stream().unwrap();
+    ///
+    /// st.scan("foo t", &s, Matching::Continue).unwrap();
+    ///
+    /// let size = st.compressed_size().unwrap();
+    /// let mut buf = vec![0; size];
+    ///
+    /// assert!(st.compress(&mut buf[..size - 1]).is_err());
+    /// assert_eq!(st.compress(&mut buf).unwrap(), size);
+    ///
+    /// st.close(&s, Matching::Terminate).unwrap();
+    /// ```
+    pub fn compressed_size(&self) -> Result<usize> {
+        let mut size = MaybeUninit::uninit();
+
+        unsafe {
+            let res = ffi::hs_compress_stream(self.as_ptr(), ptr::null_mut(), 0, size.as_mut_ptr());
+
+            if res == ffi::HS_INSUFFICIENT_SPACE {
+                Ok(size.assume_init())
+            } else {
+                res.map(|_| size.assume_init())
+            }
+        }
+    }
+
+    /// Creates a compressed representation of the provided stream in a new allocated buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    ///
+    /// let s = db.alloc_scratch().unwrap();
+    /// let st = db.open_stream().unwrap();
+    ///
+    /// let mut matches = vec![];
+    ///
+    /// let mut callback = |_, from, to, _| {
+    ///     matches.push((from, to));
+    ///
+    ///     Matching::Continue
+    /// };
+    ///
+    /// st.scan("foo t", &s, &mut callback).unwrap();
+    ///
+    /// let buf = st.compress_to_vec().unwrap();
+    /// st.close(&s, Matching::Terminate).unwrap();
+    ///
+    /// let st2 = db.expand_stream(&buf).unwrap();
+    /// st2.scan("est bar", &s, &mut callback).unwrap();
+    /// st2.close(&s, &mut callback).unwrap();
+    ///
+    /// assert_eq!(matches, vec![(4, 8)]);
+    /// ```
+    pub fn compress_to_vec(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![0; self.compressed_size()?];
+        let len = self.compress(&mut buf)?;
+
+        buf.truncate(len);
+
+        Ok(buf)
+    }
+
     /// Decompresses a compressed representation created by `StreamRef::compress` on top of the stream.
     /// The stream will first be reset (reporting any EOD matches).
     ///
@@ -264,7 +482,7 @@ impl StreamRef {
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_reset_and_expand_stream(
+            let res = ffi::hs_reset_and_expand_stream(
                 self.as_ptr(),
                 buf.as_ptr() as *const _,
                 buf.len(),
@@ -272,7 +490,9 @@ impl StreamRef {
                 callback,
                 userdata,
             )
-            .ok()
+            .ok();
+
+            resume_panic(res).map_err(hint_scratch)
         }
     }
 }
@@ -0,0 +1,291 @@
+use std::collections::hash_map::{Entry, HashMap};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::common::{Database, Streaming};
+use crate::runtime::{MatchEventHandler, Matching, Scanner, Stream};
+
+/// The streams closed or dropped by `StreamMap::shutdown`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// The number of streams closed before the deadline, whose end of data matches were delivered.
+    pub closed: usize,
+    /// The number of streams closed after the deadline, whose end of data matches were dropped.
+    pub dropped: usize,
+    /// The number of streams failed to close, whose end of data matches may be partially delivered.
+    pub failed: usize,
+    /// The number of streams still scanned after the deadline, which are left to be freed by their scans.
+    pub in_flight: usize,
+}
+
+/// An opened stream, which is taken out when it's closed.
+type Slot = Arc<Mutex<Option<Stream>>>;
+
+/// The keyed streams of a streaming database, like the streams of the network flows.
+///
+/// A stream is opened when its key is first scanned, and is closed by `StreamMap::close` or `StreamMap::shutdown`.
+/// The scans of a stream are serialized, while the different streams are scanned concurrently,
+/// the scratch space is managed per thread.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::time::{Duration, Instant};
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::StreamMap;
+/// let db: StreamingDatabase = pattern! {"test$"; SOM_LEFTMOST}.build().unwrap();
+/// let streams = StreamMap::new(db).unwrap();
+///
+/// streams.scan("a", "foo te", ()).unwrap();
+/// streams.scan("b", "foo", ()).unwrap();
+/// streams.scan("a", "st", ()).unwrap();
+///
+/// let mut matches = vec![];
+/// let report = streams
+///     .shutdown(Instant::now() + Duration::from_secs(1), |key: &&str, _, from, to| {
+///         matches.push((*key, from..to));
+///         Matching::Continue
+///     })
+///     .unwrap();
+///
+/// assert_eq!(matches, vec![("a", 4..8)]);
+/// assert_eq!(report.closed, 2);
+/// assert!(streams.scan("a", "test", ()).is_err());
+/// ```
+pub struct StreamMap<K> {
+    scanner: Scanner<Streaming>,
+    streams: Mutex<HashMap<K, Slot>>,
+    shutdown: AtomicBool,
+}
+
+impl<K: Eq + Hash> StreamMap<K> {
+    /// Constructs an empty map of the streams of the database.
+    pub fn new(db: Database<Streaming>) -> Result<Self> {
+        Ok(StreamMap {
+            scanner: Scanner::new(db)?,
+            streams: Mutex::new(HashMap::new()),
+            shutdown: AtomicBool::new(false),
+        })
+    }
+
+    /// The number of opened streams.
+    pub fn len(&self) -> usize {
+        self.streams.lock().map_or(0, |streams| streams.len())
+    }
+
+    /// Returns `true` if there is no opened stream.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the map has been shut down.
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::Acquire)
+    }
+
+    /// Write data to the stream of the key, opening it if not exists.
+    pub fn scan<D, F>(&self, key: K, data: D, on_match_event: F) -> Result<()>
+    where
+        D: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        let slot = {
+            let mut streams = self.lock()?;
+
+            if self.is_shutdown() {
+                bail!("stream map is shut down");
+            }
+
+            match streams.entry(key) {
+                Entry::Occupied(e) => e.get().clone(),
+                Entry::Vacant(e) => e
+                    .insert(Arc::new(Mutex::new(Some(self.scanner.open_stream()?))))
+                    .clone(),
+            }
+        };
+        let stream = slot.lock().map_err(|_| anyhow!("stream poisoned"))?;
+
+        match stream.as_ref() {
+            Some(stream) => self.scanner.scan_stream(stream, data, on_match_event),
+            None => bail!("stream is closed"),
+        }
+    }
+
+    /// Close the stream of the key, returning `false` if it's not opened.
+    pub fn close<F>(&self, key: &K, on_match_event: F) -> Result<bool>
+    where
+        F: MatchEventHandler,
+    {
+        let slot = {
+            let mut streams = self.lock()?;
+
+            if self.is_shutdown() {
+                bail!("stream map is shut down");
+            }
+
+            streams.remove(key)
+        };
+
+        match slot.as_ref().and_then(take_stream) {
+            Some(stream) => self.scanner.close_stream(stream, on_match_event).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Stop accepting scans, wait for the scans in flight until the deadline, and close all the streams.
+    ///
+    /// The end of data matches of the streams closed before the deadline are delivered to the callback,
+    /// the remaining streams are closed without delivering the matches, and counted as dropped.
+    /// A stream failed to close is counted as failed, and the remaining streams are still closed.
+    /// A stream still scanned after the deadline is counted as in flight, and freed when its scan returns.
+    pub fn shutdown<F>(&self, deadline: Instant, mut on_match_event: F) -> Result<ShutdownReport>
+    where
+        F: FnMut(&K, u32, u64, u64) -> Matching,
+    {
+        let streams = {
+            let mut streams = self.lock()?;
+
+            self.shutdown.store(true, Ordering::Release);
+
+            streams.drain().collect::<Vec<_>>()
+        };
+        let mut report = ShutdownReport::default();
+
+        for (key, slot) in streams {
+            let stream = match take_stream_until(&slot, deadline) {
+                Ok(Some(stream)) => stream,
+                Ok(None) => continue,
+                Err(_) => {
+                    report.in_flight += 1;
+                    continue;
+                }
+            };
+
+            let res = if Instant::now() < deadline {
+                self.scanner
+                    .close_stream(stream, |id, from, to, _| on_match_event(&key, id, from, to))
+                    .map(|_| &mut report.closed)
+            } else {
+                self.scanner.close_stream(stream, ()).map(|_| &mut report.dropped)
+            };
+
+            match res {
+                Ok(count) => *count += 1,
+                Err(_) => report.failed += 1,
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<K, Slot>>> {
+        self.streams.lock().map_err(|_| anyhow!("stream map poisoned"))
+    }
+}
+
+/// Take the stream out of the slot, waiting for the scan in flight.
+///
+/// A stream poisoned by a panicking callback can still be closed.
+fn take_stream(slot: &Slot) -> Option<Stream> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner).take()
+}
+
+/// Take the stream out of the slot, waiting for the scan in flight until the deadline.
+fn take_stream_until(slot: &Slot, deadline: Instant) -> Result<Option<Stream>, TryLockError<()>> {
+    loop {
+        match slot.try_lock() {
+            Ok(mut stream) => return Ok(stream.take()),
+            Err(TryLockError::Poisoned(err)) => return Ok(err.into_inner().take()),
+            Err(TryLockError::WouldBlock) if Instant::now() < deadline => thread::sleep(Duration::from_millis(1)),
+            Err(TryLockError::WouldBlock) => return Err(TryLockError::WouldBlock),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_stream_map_shutdown() {
+        let db: StreamingDatabase = pattern! {"test$"; SOM_LEFTMOST}.build().unwrap();
+        let streams = StreamMap::new(db).unwrap();
+
+        for key in 0..4 {
+            streams.scan(key, "test", ()).unwrap();
+        }
+
+        assert_eq!(streams.len(), 4);
+        assert!(streams.close(&0, ()).unwrap());
+        assert!(!streams.close(&0, ()).unwrap());
+
+        let slot = streams.lock().unwrap()[&1].clone();
+        let busy = slot.lock().unwrap();
+
+        let mut matches = 0;
+        let report = streams
+            .shutdown(Instant::now(), |_, _, _, _| {
+                matches += 1;
+                Matching::Continue
+            })
+            .unwrap();
+
+        assert_eq!(
+            report,
+            ShutdownReport {
+                closed: 0,
+                dropped: 2,
+                failed: 0,
+                in_flight: 1,
+            }
+        );
+        assert!(busy.is_some());
+        assert_eq!(matches, 0);
+        assert!(streams.is_empty());
+        assert!(streams.is_shutdown());
+        assert!(streams.scan(1, "test", ()).is_err());
+        assert!(streams.close(&1, ()).is_err());
+    }
+
+    #[test]
+    fn test_stream_map_concurrent_scans() {
+        let db: StreamingDatabase = pattern! {"test$"}.build().unwrap();
+        let streams = Arc::new(StreamMap::new(db).unwrap());
+
+        let threads = (0..4)
+            .map(|key| {
+                let streams = streams.clone();
+
+                std::thread::spawn(move || {
+                    for data in &["foo te", "st"] {
+                        streams.scan(key, data, ()).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let mut keys = vec![];
+        let report = streams
+            .shutdown(Instant::now() + Duration::from_secs(60), |&key, _, _, _| {
+                keys.push(key);
+                Matching::Continue
+            })
+            .unwrap();
+
+        keys.sort_unstable();
+
+        assert_eq!(report.closed, 4);
+        assert_eq!(keys, vec![0, 1, 2, 3]);
+    }
+}
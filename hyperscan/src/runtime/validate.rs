@@ -0,0 +1,194 @@
+use anyhow::{Context, Result};
+
+use crate::common::{runtime_version, Block, DatabaseRef, Mode, Streaming, Vectored};
+use crate::errors::{AsResult, Error};
+use crate::ffi;
+use crate::runtime::{ScratchRef, StreamRef};
+
+impl<T: Mode> DatabaseRef<T> {
+    /// Check the database and scratch space can be used together for a scan.
+    ///
+    /// The mode, version and CPU features of the database are cross-checked with its type and the linked runtime,
+    /// and the scratch space must be allocated (or grown) for the database.
+    ///
+    /// The scan calls check them automatically in debug builds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// # use hyperscan::Error;
+    /// let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+    /// let other: VectoredDatabase = pattern! {"foobar"}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// assert!(db.validate_for_scan(&s).is_ok());
+    ///
+    /// let err = other.validate_for_scan(&s).unwrap_err();
+    ///
+    /// assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ScratchMismatch));
+    /// ```
+    pub fn validate_for_scan(&self, scratch: &ScratchRef) -> Result<()> {
+        unsafe { ffi::hs_valid_platform().ok() }.context("the host doesn't support Hyperscan")?;
+
+        let info = self.database_info()?;
+        let mode = mode_name::<T>();
+
+        if !info.mode.starts_with(mode) {
+            return Err(Error::DbModeError)
+                .with_context(|| format!("the {} database was compiled for {} mode", mode, info.mode));
+        }
+
+        let version = runtime_version()?;
+
+        if info.version != version {
+            return Err(Error::DbVersionError).with_context(|| {
+                format!(
+                    "the database was compiled by Hyperscan {}, but the runtime is {}",
+                    info.version, version
+                )
+            });
+        }
+
+        if let Some(feature) = info.features.iter().find(|feature| !host_supports(feature)) {
+            return Err(Error::DbPlatformError)
+                .with_context(|| format!("the database requires CPU feature {} unsupported by the host", feature));
+        }
+
+        self.validate_scratch(scratch)
+            .context("the scratch space must be allocated with `alloc_scratch` or grown with `realloc_scratch`")
+    }
+}
+
+impl StreamRef {
+    /// Check the scratch space can be used with the stream.
+    ///
+    /// The database of a stream is unknown, so an empty block of data is written to the stream,
+    /// which fails if the scratch space was not allocated (or grown) for the database the stream was opened from.
+    ///
+    /// The stream scan calls check it automatically in debug builds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// # use hyperscan::Error;
+    /// let db: StreamingDatabase = "/foo.*bar/L\n/test$/L".parse().unwrap();
+    /// let other: BlockDatabase = pattern! {"test"}.build().unwrap();
+    /// let st = db.open_stream().unwrap();
+    ///
+    /// assert!(st.validate(&db.alloc_scratch().unwrap()).is_ok());
+    ///
+    /// let err = st.validate(&other.alloc_scratch().unwrap()).unwrap_err();
+    ///
+    /// assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ScratchMismatch));
+    /// ```
+    pub fn validate(&self, scratch: &ScratchRef) -> Result<()> {
+        self.scan_unchecked(b"", scratch, ())
+            .map_err(|err| match err.downcast_ref::<Error>() {
+                Some(Error::Invalid) => anyhow::Error::new(Error::ScratchMismatch)
+                    .context("the scratch space must be allocated for the database the stream was opened from"),
+                _ => err,
+            })
+    }
+}
+
+fn mode_name<T: Mode>() -> &'static str {
+    match T::ID {
+        Block::ID => "BLOCK",
+        Streaming::ID => "STREAM",
+        Vectored::ID => "VECTORED",
+        _ => T::NAME,
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn host_supports(feature: &str) -> bool {
+    match feature {
+        "AVX2" => is_x86_feature_detected!("avx2"),
+        "AVX512" => is_x86_feature_detected!("avx512bw"),
+        "AVX512VBMI" => is_x86_feature_detected!("avx512vbmi"),
+        _ => true,
+    }
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+fn host_supports(_feature: &str) -> bool {
+    true
+}
+
+#[cfg(test)]
+pub mod tests {
+    use foreign_types::ForeignType;
+
+    use crate::common::{Block, Database};
+    use crate::prelude::*;
+    use crate::Error;
+
+    #[test]
+    fn test_validate_for_scan() {
+        let db: BlockDatabase = "test".parse().unwrap();
+        let db2: VectoredDatabase = "foobar".parse().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        db.validate_for_scan(&s).unwrap();
+
+        let err = db2.validate_for_scan(&s).unwrap_err();
+
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ScratchMismatch));
+
+        let db3: StreamingDatabase = "test".parse().unwrap();
+        let db3: Database<Block> = unsafe { Database::from_ptr(db3.into_ptr()) };
+
+        let err = db3.validate_for_scan(&s).unwrap_err();
+
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::DbModeError));
+    }
+
+    #[test]
+    fn test_validate_runtime_version() {
+        let db: BlockDatabase = "test".parse().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        assert_eq!(db.version().unwrap(), crate::common::runtime_version().unwrap());
+
+        db.validate_for_scan(&s).unwrap();
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_validate_before_scan() {
+        let db: BlockDatabase = "test".parse().unwrap();
+        let db2: VectoredDatabase = "foobar".parse().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let err = db2.scan(vec!["foo", "bar"], &s, ()).unwrap_err();
+
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ScratchMismatch));
+        assert!(format!("{:#}", err).contains("alloc_scratch"));
+
+        let db3: StreamingDatabase = "test".parse().unwrap();
+        let st = db3.open_stream().unwrap();
+
+        let err = st.scan("test", &s, ()).unwrap_err();
+
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ScratchMismatch));
+        assert!(format!("{:#}", err).contains("opened from"));
+    }
+
+    #[test]
+    fn test_validate_stream() {
+        let db: StreamingDatabase = "/foo.*bar/L\n/test$/L".parse().unwrap();
+        let db2: BlockDatabase = "test".parse().unwrap();
+        let st = db.open_stream().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        st.validate(&s).unwrap();
+
+        let err = st.validate(&db2.alloc_scratch().unwrap()).unwrap_err();
+
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ScratchMismatch));
+
+        st.close(&s, ()).unwrap();
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::VecDeque;
+use std::io::IoSlice;
+
+use libc::c_uint;
+
+/// A block of data which can be scanned as part of a vectored scan.
+pub trait VectoredBlock {
+    /// Returns the data of the block.
+    fn as_block(&self) -> &[u8];
+}
+
+impl VectoredBlock for [u8] {
+    fn as_block(&self) -> &[u8] {
+        self
+    }
+}
+
+impl VectoredBlock for str {
+    fn as_block(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl VectoredBlock for Vec<u8> {
+    fn as_block(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl VectoredBlock for String {
+    fn as_block(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl VectoredBlock for IoSlice<'_> {
+    fn as_block(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl VectoredBlock for bytes::Bytes {
+    fn as_block(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl VectoredBlock for bytes::BytesMut {
+    fn as_block(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
+impl<T: VectoredBlock + ?Sized> VectoredBlock for &T {
+    fn as_block(&self) -> &[u8] {
+        (**self).as_block()
+    }
+}
+
+/// A collection of data blocks which can be scanned by a vectored database.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::io::IoSlice;
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::VectoredScannable;
+/// struct Packet {
+///     header: Vec<u8>,
+///     payload: Vec<u8>,
+/// }
+///
+/// impl VectoredScannable for Packet {
+///     fn for_each_block<'a, F: FnMut(&'a [u8])>(&'a self, mut f: F) {
+///         f(&self.header);
+///         f(&self.payload);
+///     }
+/// }
+///
+/// let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut matches = vec![];
+///
+/// let packet = Packet { header: b"foo te".to_vec(), payload: b"st bar".to_vec() };
+/// db.scan(&packet, &s, |_, from, to, _| {
+///     matches.push(from..to);
+///     Matching::Continue
+/// }).unwrap();
+///
+/// let slices = [IoSlice::new(b"foo"), IoSlice::new(b"test")];
+/// db.scan(&slices[..], &s, |_, from, to, _| {
+///     matches.push(from..to);
+///     Matching::Continue
+/// }).unwrap();
+///
+/// assert_eq!(matches, vec![4..8, 3..7]);
+/// ```
+pub trait VectoredScannable {
+    /// Calls a closure on each data block to be scanned, in order.
+    fn for_each_block<'a, F: FnMut(&'a [u8])>(&'a self, f: F);
+}
+
+impl<T: VectoredBlock> VectoredScannable for [T] {
+    fn for_each_block<'a, F: FnMut(&'a [u8])>(&'a self, mut f: F) {
+        self.iter().for_each(|block| f(block.as_block()))
+    }
+}
+
+impl<T: VectoredBlock> VectoredScannable for Vec<T> {
+    fn for_each_block<'a, F: FnMut(&'a [u8])>(&'a self, f: F) {
+        self.as_slice().for_each_block(f)
+    }
+}
+
+impl<T: VectoredBlock> VectoredScannable for VecDeque<T> {
+    fn for_each_block<'a, F: FnMut(&'a [u8])>(&'a self, mut f: F) {
+        self.iter().for_each(|block| f(block.as_block()))
+    }
+}
+
+impl<V: VectoredScannable + ?Sized> VectoredScannable for &V {
+    fn for_each_block<'a, F: FnMut(&'a [u8])>(&'a self, f: F) {
+        (**self).for_each_block(f)
+    }
+}
+
+/// A reusable buffer of the block pointers and lengths of vectored scans.
+///
+/// The buffer is emptied after each scan, it never holds a pointer to the scanned data between scans.
+#[derive(Debug, Default)]
+pub struct VectoredBuffer {
+    pub(crate) ptrs: Vec<*const i8>,
+    pub(crate) lens: Vec<c_uint>,
+}
+
+unsafe impl Send for VectoredBuffer {}
+
+impl VectoredBuffer {
+    /// Constructs an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs an empty buffer for at least `capacity` blocks.
+    pub fn with_capacity(capacity: usize) -> Self {
+        VectoredBuffer {
+            ptrs: Vec::with_capacity(capacity),
+            lens: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// The number of blocks the buffer can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.ptrs.capacity().min(self.lens.capacity())
+    }
+
+    pub(crate) fn push(&mut self, block: &[u8]) {
+        self.ptrs.push(block.as_ptr() as *const i8);
+        self.lens.push(block.len() as c_uint);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.ptrs.clear();
+        self.lens.clear();
+    }
+}
+
+/// Clears the borrowed buffer when dropped, including when a callback panic is resumed by the scan.
+pub(crate) struct ClearOnDrop<'a>(pub(crate) &'a mut VectoredBuffer);
+
+impl Drop for ClearOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.clear()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::VecDeque;
+    use std::io::IoSlice;
+    use std::ops::Range;
+
+    use crate::prelude::*;
+
+    use super::*;
+
+    fn scan<V: VectoredScannable>(db: &VectoredDatabase, s: &Scratch, data: V) -> Vec<Range<u64>> {
+        let mut matches = vec![];
+
+        db.scan(data, s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        })
+        .unwrap();
+
+        matches
+    }
+
+    #[test]
+    fn test_vectored_scannable() {
+        let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let bufs: &[&[u8]] = &[b"foo", b"test", b"bar"];
+        assert_eq!(scan(&db, &s, bufs), vec![3..7]);
+
+        let bufs = vec![b"foo t".to_vec(), b"est bar".to_vec()];
+        assert_eq!(scan(&db, &s, &bufs), vec![4..8]);
+
+        let bufs = vec![String::from("te"), String::from("st")];
+        assert_eq!(scan(&db, &s, bufs), vec![0..4]);
+
+        let bufs = [IoSlice::new(b"foo"), IoSlice::new(b"test")];
+        assert_eq!(scan(&db, &s, &bufs[..]), vec![3..7]);
+
+        let bufs = VecDeque::from(vec!["t", "e", "s", "t"]);
+        assert_eq!(scan(&db, &s, &bufs), vec![0..4]);
+    }
+
+    #[test]
+    fn test_vectored_buffer() {
+        let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut buf = VectoredBuffer::new();
+        let mut matches = vec![];
+
+        for data in &[&["foo", "test"][..], &["te", "s", "t"][..], &["bar"][..]] {
+            db.scan_with(data, &mut buf, &s, |_, from, to, _| {
+                matches.push(from..to);
+                Matching::Continue
+            })
+            .unwrap();
+        }
+
+        assert_eq!(matches, vec![3..7, 0..4]);
+        assert!(buf.capacity() >= 3);
+        assert!(buf.ptrs.is_empty());
+
+        let slices = (0..20).map(|_| IoSlice::new(b"t")).collect::<Vec<_>>();
+        let mut count = 0;
+
+        for n in &[4, 16, 20] {
+            db.scan_io_slices(&slices[..*n], &s, |_, _, _, _| {
+                count += 1;
+                Matching::Continue
+            })
+            .unwrap();
+        }
+
+        assert_eq!(count, 0);
+
+        let slices = [IoSlice::new(b"te"), IoSlice::new(b"st")];
+
+        db.scan_io_slices(&slices, &s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![3..7, 0..4, 0..4]);
+    }
+
+    #[test]
+    fn test_scan_iter() {
+        let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan_iter("foo te st".split(' ').map(String::from), &s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![3..7]);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_scan_buf() {
+        use bytes::{buf::BufExt, Bytes};
+
+        let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+        let mut on_match = |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        };
+
+        let chunks = Bytes::from("foo te").chain(Bytes::from("st bar"));
+        db.scan_buf(chunks, &s, &mut on_match).unwrap();
+
+        let chunks = Bytes::from("foo te").chain(Bytes::from("st bar")).take(9);
+        db.scan_buf(chunks, &s, &mut on_match).unwrap();
+
+        assert_eq!(matches, vec![4..8, 4..8]);
+    }
+}
@@ -0,0 +1,44 @@
+//! The allocators are global to the process, so they are tested in a dedicated test binary.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use hyperscan::prelude::*;
+use hyperscan::Allocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static FREED: AtomicUsize = AtomicUsize::new(0);
+
+struct Counting;
+
+impl Allocator for Counting {
+    fn alloc(size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(1, Ordering::SeqCst);
+
+        unsafe { libc::malloc(size) as *mut _ }
+    }
+
+    unsafe fn free(ptr: *mut u8) {
+        FREED.fetch_add(1, Ordering::SeqCst);
+
+        libc::free(ptr as *mut _)
+    }
+}
+
+#[test]
+fn test_misc_allocator() {
+    let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+
+    unsafe { hyperscan::set_misc_allocator::<Counting>() }.unwrap();
+
+    let info = db.info();
+    let buf = db.serialize();
+
+    unsafe { hyperscan::reset_allocator() }.unwrap();
+
+    assert!(info.unwrap().starts_with("Version:"));
+    assert!(!buf.unwrap().is_empty());
+    assert!(ALLOCATED.load(Ordering::SeqCst) >= 2);
+    assert!(FREED.load(Ordering::SeqCst) >= 2);
+
+    assert!(db.info().unwrap().starts_with("Version:"));
+}